@@ -0,0 +1,39 @@
+//! Scaffolding shared by the apps
+
+use std::path::PathBuf;
+
+use {glam::Vec3, rokol::app as ra};
+
+use crate::{
+    camera::{OrbitCamera, Projection},
+    input::Input,
+};
+
+/// Path of a file in `assets/`
+pub fn asset_path(rel: &str) -> PathBuf {
+    let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    root.join("assets").join(rel)
+}
+
+/// Orbit camera at `pos` looking at the origin with 60 degrees of vertical FoV
+pub fn orbit_camera(pos: Vec3) -> OrbitCamera {
+    let mut cam = OrbitCamera::new(
+        Vec3::zero(),
+        1.0,
+        Projection::perspective(
+            std::f32::consts::FRAC_PI_3,
+            ra::width() as f32 / ra::height() as f32,
+        ),
+    );
+    cam.set_pos(pos);
+    cam
+}
+
+/// Drag to rotate, scroll to zoom
+pub fn orbit_by_mouse(cam: &mut OrbitCamera, input: &Input) {
+    if input.is_mouse_down(ra::MouseButton::Left) {
+        let [dx, dy] = input.mouse_delta();
+        cam.rotate(dx, dy);
+    }
+    cam.zoom(input.scroll()[1]);
+}
@@ -1,18 +1,21 @@
 //! Textured cube
 
-use std::path::PathBuf;
-
 use {
     glam::Mat4,
     rokol::{app as ra, gfx as rg},
 };
 
 use crate::{
-    gfx::{Shader, StaticMesh, Texture2dDrop, TextureBuilder},
-    shaders::{self, CubeVertex},
+    apps::common,
+    camera::{Camera, OrbitCamera},
+    gfx::{MipFilter, Shader, StaticMesh, Texture2dDrop, TextureBuilder},
+    input::Input,
+    resize::ResizeTracker,
+    shaders::{self, CubeUniforms, CubeVertex},
+    time::Time,
 };
 
-fn gen_cube_mesh() -> StaticMesh<CubeVertex> {
+pub(crate) fn gen_cube_mesh() -> StaticMesh<CubeVertex> {
     StaticMesh::new_16(
         // vertices
         &[
@@ -65,6 +68,16 @@ pub struct CubeApp {
     shd: Shader,
     tex: Texture2dDrop,
     mesh: StaticMesh<CubeVertex>,
+    cam: OrbitCamera,
+    input: Input,
+    resize: ResizeTracker,
+    time: Time,
+}
+
+impl Default for CubeApp {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CubeApp {
@@ -72,65 +85,56 @@ impl CubeApp {
         let color = [100.0 / 255.0, 149.0 / 255.0, 237.0 / 255.0, 1.0];
         let shd = shaders::cube();
 
-        let tex = {
-            let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
-            let path = root.join("assets/tex/container.jpg");
-            TextureBuilder::from_path(&path).unwrap().build_texture()
-        };
+        let tex = TextureBuilder::from_path(&common::asset_path("tex/container.jpg"))
+            .unwrap()
+            .mipmaps(MipFilter::Box)
+            .build_texture();
 
         let mut mesh = self::gen_cube_mesh();
         mesh.bind_img(tex.img(), 0);
 
+        let cam = common::orbit_camera([2.0, 2.0, 4.0].into());
+
         Self {
             pa: rg::PassAction::clear(color),
             shd,
             tex,
             mesh,
+            cam,
+            input: Input::new(),
+            resize: ResizeTracker::from_window(),
+            time: Time::new(),
         }
     }
 }
 
 impl rokol::app::RApp for CubeApp {
+    fn event(&mut self, ev: &ra::Event) {
+        self.input.on_event(ev);
+        self.resize.on_event(ev);
+    }
+
     fn frame(&mut self) {
+        self.time.tick();
+        self.resize.apply(&mut [&mut self.cam.proj]);
+        self.shd.hot_reload();
+
+        common::orbit_by_mouse(&mut self.cam, &self.input);
+        self.input.end_frame();
+
         rg::begin_default_pass(&self.pa, ra::width(), ra::height());
 
         self.shd.apply_pip();
 
         {
-            // TODO: rotate
-            // let spd =
-            // let rot_x = glam::Mat4::rotate(rx, [1.0f, 0.0f, 0.0f]);
-            // let rot_y = glam::Mat4::rotate(ry, [0.0f, 1.0f, 0.0f]);
-            // let model = rot_x * rot_y;
-
-            // right-handed matrices
-            let view = Mat4::look_at_rh(
-                // camera position
-                [2.0, 2.0, 4.0].into(),
-                // focal point
-                [0.0, 0.0, 0.0].into(),
-                // up direction
-                [0.0, 1.0, 0.0].into(),
-            );
-
-            let ratio = ra::width() as f32 / ra::height() as f32;
-            let proj = Mat4::perspective_rh(
-                3.14 / 3.0, // fov_y_radian
-                ratio,      // aspect_ratio
-                0.01,       // z_near
-                100.0,      // z_far
-            );
+            // rotate with time (radians per second)
+            let t = self.time.elapsed().as_secs_f32();
+            let model = Mat4::from_rotation_x(t * 0.5) * Mat4::from_rotation_y(t);
 
             // column-major matrix notation (v' = Mv)
-            let vp = proj * view;
-
-            let bytes: &[u8] = unsafe {
-                std::slice::from_raw_parts(
-                    vp.as_ref() as *const _ as *const _,
-                    std::mem::size_of::<Mat4>(),
-                )
-            };
-            rg::apply_uniforms(rg::ShaderStage::Vs, 0, bytes);
+            let mvp = self.cam.view_proj() * model;
+
+            self.shd.apply_vs(&CubeUniforms { mvp });
         }
 
         self.mesh.draw_all();
@@ -0,0 +1,98 @@
+//! Host of the demo apps, switched with PageUp/PageDown keys
+
+use rokol::app::{self as ra, RApp};
+
+use crate::apps::APPS;
+
+/// Owns one of the registered [`APPS`] and forwards callbacks to it
+///
+/// PageDown switches to the next app and PageUp switches to the previous one.
+pub struct AppHost {
+    /// Index in [`APPS`]
+    ix: usize,
+    /// `None` only while switching apps
+    app: Option<Box<dyn RApp>>,
+}
+
+impl std::fmt::Debug for AppHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppHost")
+            .field("app", &APPS[self.ix].name)
+            .finish()
+    }
+}
+
+impl AppHost {
+    /// * `ix`: index in [`APPS`]
+    pub fn new(ix: usize) -> Self {
+        log::info!("starting app `{}`", APPS[ix].name);
+        Self {
+            ix,
+            app: Some((APPS[ix].new)()),
+        }
+    }
+
+    /// Name of the current app
+    pub fn name(&self) -> &'static str {
+        APPS[self.ix].name
+    }
+
+    /// Replaces the current app with the one at `ix` in [`APPS`]
+    pub fn switch(&mut self, ix: usize) {
+        assert!(ix < APPS.len(), "app index out of range: {}", ix);
+
+        // clean up and drop the current app first so that its GPU resources are released before
+        // creating the next app's ones
+        if let Some(mut app) = self.app.take() {
+            app.cleanup();
+        }
+
+        log::info!(
+            "switching app `{}` -> `{}`",
+            APPS[self.ix].name,
+            APPS[ix].name
+        );
+        self.ix = ix;
+        self.app = Some((APPS[ix].new)());
+    }
+
+    pub fn next(&mut self) {
+        self.switch((self.ix + 1) % APPS.len());
+    }
+
+    pub fn prev(&mut self) {
+        self.switch((self.ix + APPS.len() - 1) % APPS.len());
+    }
+}
+
+impl RApp for AppHost {
+    fn frame(&mut self) {
+        if let Some(app) = self.app.as_mut() {
+            app.frame();
+        }
+    }
+
+    fn event(&mut self, ev: &ra::Event) {
+        if ev.type_ == ra::EventType::KeyDown as u32 && !ev.key_repeat {
+            if ev.key_code == ra::Key::PageDown as u32 {
+                self.next();
+                return;
+            }
+
+            if ev.key_code == ra::Key::PageUp as u32 {
+                self.prev();
+                return;
+            }
+        }
+
+        if let Some(app) = self.app.as_mut() {
+            app.event(ev);
+        }
+    }
+
+    fn cleanup(&mut self) {
+        if let Some(app) = self.app.as_mut() {
+            app.cleanup();
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Lit cubes rendered in HDR with bloom
+//!
+//! Space toggles Phong / Blinn-Phong, T switches the tone mapping, B toggles bloom and Up / Down
+//! change the exposure.
+
+use {
+    glam::{Mat4, Vec3},
+    rokol::{app as ra, gfx as rg},
+};
+
+use crate::{
+    apps::common,
+    bloom::Bloom,
+    camera::{Camera, OrbitCamera},
+    gfx::{MipFilter, StaticMesh, TextureBuilder},
+    hdr::Hdr,
+    input::Input,
+    light::{DirLight, Light, LightColors, Lights, PointLight, SpotLight},
+    material::{DefaultMaps, MapKind, Material},
+    resize::ResizeTracker,
+    shaders::{LitVertex, LitVsUniforms, ShaderCache},
+    time::Time,
+};
+
+/// Cube of size 2 with a face normal and UV per vertex
+pub(crate) fn gen_lit_cube_mesh() -> StaticMesh<LitVertex> {
+    // (normal, u axis, v axis)
+    let faces = [
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ];
+
+    let mut verts: Vec<LitVertex> = Vec::with_capacity(6 * 4);
+    let mut indices: Vec<u16> = Vec::with_capacity(6 * 6);
+
+    for (i, (n, u, v)) in faces.iter().enumerate() {
+        let (n, u, v) = (Vec3::from(*n), Vec3::from(*u), Vec3::from(*v));
+        for &(su, sv) in &[(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let pos = n + u * su + v * sv;
+            let uv = [(su + 1.0) / 2.0, (sv + 1.0) / 2.0];
+            verts.push((pos, n, uv).into());
+        }
+
+        // clockwise (front face of sokol by default)
+        let base = (i * 4) as u16;
+        indices.extend_from_slice(&[base, base + 2, base + 1, base, base + 3, base + 2]);
+    }
+
+    StaticMesh::new_16(&verts, &indices)
+}
+
+/// Positions of the cubes
+const CUBES: &[[f32; 3]] = &[
+    [0.0, 0.0, 0.0],
+    [3.0, 0.5, -2.0],
+    [-2.5, -0.5, -3.0],
+    [0.0, -4.0, 0.0],
+];
+
+#[derive(Debug)]
+pub struct LightingApp {
+    pa: rg::PassAction,
+    /// Variants of the `lit` shader
+    shaders: ShaderCache,
+    /// Material of the cubes
+    container: Material,
+    floor: Material,
+    defaults: DefaultMaps,
+    mesh: StaticMesh<LitVertex>,
+    lights: Lights,
+    /// Index of the orbiting point light in `lights`
+    point: usize,
+    hdr: Hdr,
+    cam: OrbitCamera,
+    input: Input,
+    resize: ResizeTracker,
+    time: Time,
+}
+
+impl Default for LightingApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightingApp {
+    pub fn new() -> Self {
+        let container = {
+            let diffuse = TextureBuilder::from_path(&common::asset_path("tex/container.jpg"))
+                .unwrap()
+                .srgb(true)
+                .mipmaps(MipFilter::Box)
+                .build_texture();
+
+            let mut material = Material::new();
+            material.set_map(MapKind::Diffuse, diffuse);
+            material
+        };
+
+        let mut floor = Material::new();
+        floor.set_tint([0.6, 0.6, 0.6, 1.0]).set_shininess(8.0);
+
+        let mut lights = Lights::new();
+        lights.push(DirLight::new(
+            [-0.2, -1.0, -0.3].into(),
+            LightColors::from_color([0.3, 0.3, 0.35].into()),
+        ));
+        // bright enough to bloom
+        let point = lights.push(PointLight::new(
+            [2.0, 1.0, 2.0].into(),
+            LightColors::from_color([4.0, 3.0, 2.0].into()),
+        ));
+        lights.push(SpotLight::new(
+            [0.0, 6.0, 0.0].into(),
+            [0.0, -1.0, 0.0].into(),
+            LightColors::from_color([0.5, 1.0, 3.0].into()),
+        ));
+
+        let cam = common::orbit_camera([4.0, 3.0, 8.0].into());
+
+        let (w, h) = (ra::width() as u32, ra::height() as u32);
+        let mut hdr = Hdr::new(w, h);
+        hdr.set_bloom(Some(Bloom::new(w, h)));
+
+        Self {
+            pa: rg::PassAction::clear([0.0, 0.0, 0.0, 1.0]),
+            shaders: ShaderCache::new(),
+            container,
+            floor,
+            defaults: DefaultMaps::new(),
+            mesh: self::gen_lit_cube_mesh(),
+            lights,
+            point,
+            hdr,
+            cam,
+            input: Input::new(),
+            resize: ResizeTracker::from_window(),
+            time: Time::new(),
+        }
+    }
+
+    fn handle_input(&mut self) {
+        if self.input.is_pressed(ra::Key::Space) {
+            self.lights.model = self.lights.model.toggle();
+            log::info!("shading model: {:?}", self.lights.model);
+        }
+
+        if self.input.is_pressed(ra::Key::T) {
+            self.hdr.set_tone_map(self.hdr.tone_map().next());
+            log::info!("tone mapping: {:?}", self.hdr.tone_map());
+        }
+
+        if self.input.is_pressed(ra::Key::B) {
+            let [w, h] = self.hdr.size();
+            let bloom = match self.hdr.bloom() {
+                Some(_) => None,
+                None => Some(Bloom::new(w, h)),
+            };
+            log::info!("bloom: {}", bloom.is_some());
+            self.hdr.set_bloom(bloom);
+        }
+
+        // exposure doubles per second
+        let scale = 2.0_f32.powf(self.time.dt_secs());
+        let mut exposure = self.hdr.exposure();
+        if self.input.is_down(ra::Key::Up) {
+            exposure *= scale;
+        }
+        if self.input.is_down(ra::Key::Down) {
+            exposure /= scale;
+        }
+        self.hdr.set_exposure(exposure);
+
+        common::orbit_by_mouse(&mut self.cam, &self.input);
+        self.input.end_frame();
+    }
+}
+
+impl rokol::app::RApp for LightingApp {
+    fn event(&mut self, ev: &ra::Event) {
+        self.input.on_event(ev);
+        self.resize.on_event(ev);
+    }
+
+    fn frame(&mut self) {
+        self.time.tick();
+        self.resize.apply(&mut [&mut self.cam.proj, &mut self.hdr]);
+        self.shaders.hot_reload();
+        self.hdr.hot_reload();
+
+        self.handle_input();
+
+        // orbit the point light
+        let t = self.time.elapsed().as_secs_f32();
+        if let Light::Point(ref mut l) = self.lights.lights[self.point] {
+            l.pos = [3.0 * t.cos(), 1.0, 3.0 * t.sin()].into();
+        }
+
+        // scene
+        rg::begin_pass(self.hdr.scene_pass(), &self.pa);
+
+        let lights = self.lights.uniforms(self.cam.pos());
+        let view_proj = self.cam.view_proj();
+
+        for (i, pos) in CUBES.iter().enumerate() {
+            let (material, model) = if i == CUBES.len() - 1 {
+                let scale = Mat4::from_scale([8.0, 0.2, 8.0].into());
+                (&self.floor, Mat4::from_translation((*pos).into()) * scale)
+            } else {
+                let rot = Mat4::from_rotation_y(i as f32 * 0.6);
+                (&self.container, Mat4::from_translation((*pos).into()) * rot)
+            };
+
+            let shd = material.apply_pip(&mut self.shaders);
+            shd.apply_fs(&lights);
+            shd.apply_vs(&LitVsUniforms::new(model, view_proj));
+            material.apply(shd, &mut self.mesh, &self.defaults);
+            self.mesh.draw_all();
+        }
+
+        rg::end_pass();
+
+        // tone mapping
+        self.hdr.resolve(ra::width() as u32, ra::height() as u32);
+
+        rg::commit();
+    }
+}
@@ -4,8 +4,93 @@ Implementations of [`rokol::app::RApp`]
 Just a showcase.
 */
 
+mod common;
 mod cube;
+mod host;
+mod lighting;
+mod postfx;
+mod skybox;
 mod texture;
 mod triangle;
 
-pub use self::{cube::CubeApp, texture::TextureApp, triangle::TriangleApp};
+pub use self::{
+    cube::CubeApp, host::AppHost, lighting::LightingApp, postfx::PostFxApp, skybox::SkyboxApp,
+    texture::TextureApp, triangle::TriangleApp,
+};
+
+use rokol::app::RApp;
+
+/// Registered demo application
+#[derive(Debug, Clone, Copy)]
+pub struct AppEntry {
+    /// Name to select the app from command line
+    pub name: &'static str,
+    /// One-line description
+    pub desc: &'static str,
+    /// Constructor of the app
+    pub new: fn() -> Box<dyn RApp>,
+}
+
+/// All the demo applications (in order of the book)
+pub static APPS: &[AppEntry] = &[
+    AppEntry {
+        name: "triangle",
+        desc: "Ch. 1 Hello triangle",
+        new: || Box::new(TriangleApp::new()),
+    },
+    AppEntry {
+        name: "texture",
+        desc: "Ch. 1 Textures",
+        new: || Box::new(TextureApp::new()),
+    },
+    AppEntry {
+        name: "cube",
+        desc: "Ch. 1 Coordinate systems (textured cube)",
+        new: || Box::new(CubeApp::new()),
+    },
+    AppEntry {
+        name: "lighting",
+        desc: "Ch. 2 Lighting (Phong / Blinn-Phong, HDR and bloom)",
+        new: || Box::new(LightingApp::new()),
+    },
+    AppEntry {
+        name: "postfx",
+        desc: "Ch. 4 Framebuffers (post-processing)",
+        new: || Box::new(PostFxApp::new()),
+    },
+    AppEntry {
+        name: "skybox",
+        desc: "Ch. 4 Cubemaps (skybox)",
+        new: || Box::new(SkyboxApp::new()),
+    },
+];
+
+/// Finds a registered app by name (ignoring ASCII case) and returns its index in [`APPS`]
+pub fn find(name: &str) -> Option<usize> {
+    APPS.iter()
+        .position(|app| app.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_known_app() {
+        let ix = self::find("cube").unwrap();
+        assert_eq!(APPS[ix].name, "cube");
+        assert_eq!(self::find("triangle"), Some(0));
+    }
+
+    #[test]
+    fn find_unknown_app() {
+        assert_eq!(self::find("no_such_app"), None);
+        assert_eq!(self::find(""), None);
+    }
+
+    #[test]
+    fn find_ignores_case() {
+        assert_eq!(self::find("Cube"), self::find("cube"));
+        assert_eq!(self::find("SKYBOX"), self::find("skybox"));
+    }
+}
@@ -0,0 +1,133 @@
+//! Post-processing: textured cube rendered off-screen, then full-screen effects
+//!
+//! Space switches the effect chain.
+
+use {
+    glam::Mat4,
+    rokol::{app as ra, gfx as rg},
+};
+
+use crate::{
+    apps::{common, cube},
+    camera::{Camera, OrbitCamera},
+    gfx::{MipFilter, Shader, StaticMesh, Texture2dDrop, TextureBuilder},
+    input::Input,
+    postfx::{self, Effect, PostFx},
+    resize::ResizeTracker,
+    shaders::{self, CubeUniforms, CubeVertex},
+    time::Time,
+};
+
+/// Effect chains switched with Space
+const CHAINS: &[(&str, &[Effect])] = &[
+    ("none", &[]),
+    ("invert", &[Effect::invert()]),
+    ("grayscale", &[Effect::grayscale()]),
+    ("sharpen", &[Effect::kernel(postfx::SHARPEN)]),
+    ("blur", &[Effect::kernel(postfx::BLUR)]),
+    ("edge detect", &[Effect::kernel(postfx::EDGE_DETECT)]),
+    (
+        "blur + grayscale",
+        &[Effect::kernel(postfx::BLUR), Effect::grayscale()],
+    ),
+];
+
+#[derive(Debug)]
+pub struct PostFxApp {
+    pa: rg::PassAction,
+    shd: Shader,
+    tex: Texture2dDrop,
+    mesh: StaticMesh<CubeVertex>,
+    cam: OrbitCamera,
+    input: Input,
+    resize: ResizeTracker,
+    time: Time,
+    postfx: PostFx,
+    /// Index in [`CHAINS`]
+    chain: usize,
+}
+
+impl Default for PostFxApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PostFxApp {
+    pub fn new() -> Self {
+        let color = [100.0 / 255.0, 149.0 / 255.0, 237.0 / 255.0, 1.0];
+        let shd = shaders::cube();
+
+        let tex = TextureBuilder::from_path(&common::asset_path("tex/container.jpg"))
+            .unwrap()
+            .mipmaps(MipFilter::Box)
+            .build_texture();
+
+        let mut mesh = cube::gen_cube_mesh();
+        mesh.bind_img(tex.img(), 0);
+
+        let cam = common::orbit_camera([2.0, 2.0, 4.0].into());
+
+        let mut postfx = PostFx::new(ra::width() as u32, ra::height() as u32);
+        *postfx.effects_mut() = CHAINS[0].1.to_vec();
+
+        Self {
+            pa: rg::PassAction::clear(color),
+            shd,
+            tex,
+            mesh,
+            cam,
+            input: Input::new(),
+            resize: ResizeTracker::from_window(),
+            time: Time::new(),
+            postfx,
+            chain: 0,
+        }
+    }
+}
+
+impl rokol::app::RApp for PostFxApp {
+    fn event(&mut self, ev: &ra::Event) {
+        self.input.on_event(ev);
+        self.resize.on_event(ev);
+    }
+
+    fn frame(&mut self) {
+        self.time.tick();
+        self.resize
+            .apply(&mut [&mut self.cam.proj, &mut self.postfx]);
+        self.shd.hot_reload();
+        self.postfx.hot_reload();
+
+        if self.input.is_pressed(ra::Key::Space) {
+            self.chain = (self.chain + 1) % CHAINS.len();
+            log::info!("post effect: {}", CHAINS[self.chain].0);
+            *self.postfx.effects_mut() = CHAINS[self.chain].1.to_vec();
+        }
+
+        common::orbit_by_mouse(&mut self.cam, &self.input);
+        self.input.end_frame();
+
+        // scene
+        rg::begin_pass(self.postfx.scene_pass(), &self.pa);
+
+        self.shd.apply_pip();
+
+        {
+            let t = self.time.elapsed().as_secs_f32();
+            let model = Mat4::from_rotation_x(t * 0.5) * Mat4::from_rotation_y(t);
+            let mvp = self.cam.view_proj() * model;
+
+            self.shd.apply_vs(&CubeUniforms { mvp });
+        }
+
+        self.mesh.draw_all();
+
+        rg::end_pass();
+
+        // effects
+        self.postfx.render(ra::width() as u32, ra::height() as u32);
+
+        rg::commit();
+    }
+}
@@ -0,0 +1,126 @@
+//! Textured cube with a skybox
+
+use {
+    glam::Mat4,
+    rokol::{app as ra, gfx as rg},
+};
+
+use crate::{
+    apps::{common, cube},
+    camera::{Camera, OrbitCamera},
+    gfx::{CubemapBuilder, MipFilter, Shader, StaticMesh, Texture2dDrop, TextureBuilder},
+    input::Input,
+    resize::ResizeTracker,
+    shaders::{self, CubeUniforms, CubeVertex, ShaderSpec},
+    skybox::Skybox,
+    time::Time,
+};
+
+/// [`shaders::CUBE`] writing depth so that the skybox is drawn only around it
+const CUBE: ShaderSpec = ShaderSpec {
+    pip_desc: || {
+        let mut pip = (shaders::CUBE.pip_desc)();
+        pip.depth.compare = rg::CompareFunc::LessEqual as u32;
+        pip.depth.write_enabled = true;
+        pip
+    },
+    ..shaders::CUBE
+};
+
+#[derive(Debug)]
+pub struct SkyboxApp {
+    pa: rg::PassAction,
+    shd: Shader,
+    tex: Texture2dDrop,
+    mesh: StaticMesh<CubeVertex>,
+    skybox: Skybox,
+    cam: OrbitCamera,
+    input: Input,
+    resize: ResizeTracker,
+    time: Time,
+}
+
+impl Default for SkyboxApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SkyboxApp {
+    pub fn new() -> Self {
+        let color = [100.0 / 255.0, 149.0 / 255.0, 237.0 / 255.0, 1.0];
+        let shd = self::CUBE.build(&[]);
+
+        let tex = TextureBuilder::from_path(&common::asset_path("tex/container.jpg"))
+            .unwrap()
+            .mipmaps(MipFilter::Box)
+            .build_texture();
+
+        let mut mesh = cube::gen_cube_mesh();
+        mesh.bind_img(tex.img(), 0);
+
+        let skybox = {
+            let dir = common::asset_path("skybox");
+            let face = |name: &str| dir.join(format!("{}.jpg", name));
+            let tex = CubemapBuilder::from_paths([
+                &face("right"),
+                &face("left"),
+                &face("top"),
+                &face("bottom"),
+                &face("front"),
+                &face("back"),
+            ])
+            .unwrap()
+            .build_texture();
+            Skybox::new(tex)
+        };
+
+        let cam = common::orbit_camera([2.0, 2.0, 4.0].into());
+
+        Self {
+            pa: rg::PassAction::clear(color),
+            shd,
+            tex,
+            mesh,
+            skybox,
+            cam,
+            input: Input::new(),
+            resize: ResizeTracker::from_window(),
+            time: Time::new(),
+        }
+    }
+}
+
+impl rokol::app::RApp for SkyboxApp {
+    fn event(&mut self, ev: &ra::Event) {
+        self.input.on_event(ev);
+        self.resize.on_event(ev);
+    }
+
+    fn frame(&mut self) {
+        self.time.tick();
+        self.resize.apply(&mut [&mut self.cam.proj]);
+        self.shd.hot_reload();
+        self.skybox.shd_mut().hot_reload();
+
+        common::orbit_by_mouse(&mut self.cam, &self.input);
+        self.input.end_frame();
+
+        rg::begin_default_pass(&self.pa, ra::width(), ra::height());
+
+        self.shd.apply_pip();
+        {
+            let t = self.time.elapsed().as_secs_f32();
+            let model = Mat4::from_rotation_x(t * 0.5) * Mat4::from_rotation_y(t);
+            let mvp = self.cam.view_proj() * model;
+            self.shd.apply_vs(&CubeUniforms { mvp });
+        }
+        self.mesh.draw_all();
+
+        // after the scene, only on the uncovered pixels
+        self.skybox.draw(&self.cam);
+
+        rg::end_pass();
+        rg::commit();
+    }
+}
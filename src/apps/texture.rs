@@ -1,8 +1,7 @@
-use std::path::PathBuf;
-
 use rokol::{app as ra, gfx as rg};
 
 use crate::{
+    apps::common,
     gfx::{Shader, StaticMesh, Texture2dDrop, TextureBuilder},
     shaders,
 };
@@ -16,15 +15,19 @@ pub struct TextureApp {
     mesh: StaticMesh<shaders::TextureVertex>,
 }
 
+impl Default for TextureApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TextureApp {
     pub fn new() -> Self {
         let color = [100.0 / 255.0, 149.0 / 255.0, 237.0 / 255.0, 1.0];
 
         let shd = shaders::texture();
 
-        let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
-        let path = root.join("assets/tex/container.jpg");
-        let tex = TextureBuilder::from_path(&path)
+        let tex = TextureBuilder::from_path(&common::asset_path("tex/container.jpg"))
             .unwrap()
             // .filter(rg::Filter::Nearest)
             .build_texture();
@@ -62,7 +65,9 @@ impl rokol::app::RApp for TextureApp {
 }
 
 impl TextureApp {
-    fn update(&mut self) {}
+    fn update(&mut self) {
+        self.shd.hot_reload();
+    }
 
     fn render(&mut self) {
         rg::begin_default_pass(&self.pa, ra::width(), ra::height());
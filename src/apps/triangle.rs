@@ -16,6 +16,12 @@ pub struct TriangleApp {
     mesh: StaticMesh<shaders::TriangleVertex>,
 }
 
+impl Default for TriangleApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TriangleApp {
     pub fn new() -> Self {
         // set up a triangle
@@ -46,7 +52,7 @@ impl rokol::app::RApp for TriangleApp {
 
 impl TriangleApp {
     fn update(&mut self) {
-        //
+        self.shd.hot_reload();
     }
 
     fn render(&mut self) {
@@ -0,0 +1,412 @@
+/*!
+glTF 2.0 loader (`.gltf` + `.bin` or `.glb`)
+
+Only triangle primitives are loaded. Texture coordinates are flipped vertically so that they match
+the images loaded with [`TextureBuilder`] (which flips images for OpenGL).
+*/
+
+use std::{fs, path::Path};
+
+use glam::Mat4;
+
+use crate::{
+    assets::{Indices, MeshData, ModelVertex},
+    gfx::{TextureBuilder, TextureError},
+};
+
+pub type Result<T> = gltf::Result<T>;
+
+/// Encoded image (PNG or JPEG) referred to by materials
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfImage {
+    pub name: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+impl GltfImage {
+    /// Decodes the image
+    pub fn texture_builder(&self) -> std::result::Result<TextureBuilder<'static>, TextureError> {
+        TextureBuilder::from_encoded_bytes(&self.bytes)
+    }
+}
+
+/// PBR metallic-roughness material
+///
+/// Textures are indices in [`GltfModel::images`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfMaterial {
+    pub name: Option<String>,
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: [f32; 3],
+    pub double_sided: bool,
+    pub base_color_tex: Option<usize>,
+    /// Blue channel: metalness, green channel: roughness
+    pub metallic_roughness_tex: Option<usize>,
+    pub normal_tex: Option<usize>,
+    pub occlusion_tex: Option<usize>,
+    pub emissive_tex: Option<usize>,
+}
+
+/// Part of [`GltfMesh`] drawn with one material
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfPrimitive {
+    /// Index in [`GltfModel::materials`]
+    pub material: Option<usize>,
+    pub data: MeshData<ModelVertex>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+/// Node in the transform tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    /// Index in [`GltfModel::meshes`]
+    pub mesh: Option<usize>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Transform relative to the parent
+    pub local: Mat4,
+    /// Transform relative to the scene root
+    pub world: Mat4,
+}
+
+/// Loaded glTF file
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfModel {
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<GltfMaterial>,
+    pub images: Vec<GltfImage>,
+    pub nodes: Vec<GltfNode>,
+    /// Root nodes of the default scene (or of all the nodes if there's no scene)
+    pub roots: Vec<usize>,
+}
+
+impl GltfModel {
+    /// Loads `.gltf` or `.glb` file and the resources referred to from it
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(gltf::Error::Io)?;
+        Self::from_slice(&bytes, path.parent())
+    }
+
+    /// Loads glTF from memory
+    ///
+    /// * `base`: directory to resolve relative URIs. `None` if only embedded data is allowed
+    pub fn from_slice(bytes: &[u8], base: Option<&Path>) -> Result<Self> {
+        let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(bytes)?;
+        let buffers = self::load_buffers(&document, base, blob)?;
+
+        let images = document
+            .images()
+            .map(|img| {
+                Ok(GltfImage {
+                    name: img.name().map(|s| s.to_string()),
+                    bytes: self::load_image(&img, base, &buffers)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let materials = document.materials().map(self::load_material).collect();
+
+        let meshes = document
+            .meshes()
+            .map(|mesh| self::load_mesh(&mesh, &buffers))
+            .collect();
+
+        let (nodes, roots) = self::load_nodes(&document);
+
+        Ok(Self {
+            meshes,
+            materials,
+            images,
+            nodes,
+            roots,
+        })
+    }
+
+    /// Iterates through nodes with meshes, along with their world transforms
+    pub fn mesh_nodes(&self) -> impl Iterator<Item = (&GltfMesh, Mat4)> + '_ {
+        self.nodes
+            .iter()
+            .filter_map(move |node| node.mesh.map(|i| (&self.meshes[i], node.world)))
+    }
+}
+
+fn load_buffers(
+    document: &gltf::Document,
+    base: Option<&Path>,
+    mut blob: Option<Vec<u8>>,
+) -> Result<Vec<Vec<u8>>> {
+    document
+        .buffers()
+        .map(|buf| {
+            let data = match buf.source() {
+                gltf::buffer::Source::Bin => blob.take().ok_or(gltf::Error::MissingBlob)?,
+                gltf::buffer::Source::Uri(uri) => self::read_uri(uri, base)?,
+            };
+
+            if data.len() < buf.length() {
+                return Err(gltf::Error::BufferLength {
+                    buffer: buf.index(),
+                    expected: buf.length(),
+                    actual: data.len(),
+                });
+            }
+
+            Ok(data)
+        })
+        .collect()
+}
+
+/// Reads data URI (base64) or a file relative to `base`
+fn read_uri(uri: &str, base: Option<&Path>) -> Result<Vec<u8>> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let i = data
+            .find(";base64,")
+            .ok_or(gltf::Error::UnsupportedScheme)?;
+        return base64::decode(&data[i + ";base64,".len()..]).map_err(gltf::Error::Base64);
+    }
+
+    if uri.contains(':') {
+        return Err(gltf::Error::UnsupportedScheme);
+    }
+
+    let base = base.ok_or(gltf::Error::ExternalReferenceInSliceImport)?;
+    fs::read(base.join(uri)).map_err(gltf::Error::Io)
+}
+
+/// Returns encoded bytes
+fn load_image(img: &gltf::Image, base: Option<&Path>, buffers: &[Vec<u8>]) -> Result<Vec<u8>> {
+    match img.source() {
+        gltf::image::Source::View { view, .. } => {
+            let buf = &buffers[view.buffer().index()];
+            Ok(buf[view.offset()..view.offset() + view.length()].to_vec())
+        }
+        gltf::image::Source::Uri { uri, .. } => self::read_uri(uri, base),
+    }
+}
+
+fn load_material(mat: gltf::Material) -> GltfMaterial {
+    let pbr = mat.pbr_metallic_roughness();
+    let img = |tex: gltf::Texture| tex.source().index();
+
+    GltfMaterial {
+        name: mat.name().map(|s| s.to_string()),
+        base_color: pbr.base_color_factor(),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        emissive: mat.emissive_factor(),
+        double_sided: mat.double_sided(),
+        base_color_tex: pbr.base_color_texture().map(|info| img(info.texture())),
+        metallic_roughness_tex: pbr
+            .metallic_roughness_texture()
+            .map(|info| img(info.texture())),
+        normal_tex: mat.normal_texture().map(|info| img(info.texture())),
+        occlusion_tex: mat.occlusion_texture().map(|info| img(info.texture())),
+        emissive_tex: mat.emissive_texture().map(|info| img(info.texture())),
+    }
+}
+
+fn load_mesh(mesh: &gltf::Mesh, buffers: &[Vec<u8>]) -> GltfMesh {
+    let primitives = mesh
+        .primitives()
+        .filter_map(|prim| {
+            if prim.mode() != gltf::mesh::Mode::Triangles {
+                log::warn!(
+                    "skipping non-triangle primitive {} of mesh {}",
+                    prim.index(),
+                    mesh.index()
+                );
+                return None;
+            }
+
+            let reader = prim.reader(|buf| Some(&buffers[buf.index()]));
+
+            let mut verts: Vec<ModelVertex> = reader
+                .read_positions()?
+                .map(|pos| ModelVertex {
+                    pos,
+                    ..Default::default()
+                })
+                .collect();
+
+            if let Some(normals) = reader.read_normals() {
+                for (v, n) in verts.iter_mut().zip(normals) {
+                    v.normal = n;
+                }
+            }
+
+            if let Some(uvs) = reader.read_tex_coords(0) {
+                for (v, uv) in verts.iter_mut().zip(uvs.into_f32()) {
+                    // glTF: top-left origin, OpenGL: bottom-left origin
+                    v.uv = [uv[0], 1.0 - uv[1]];
+                }
+            }
+
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..verts.len() as u32).collect(),
+            };
+
+            let n_verts = verts.len();
+            Some(GltfPrimitive {
+                material: prim.material().index(),
+                data: MeshData {
+                    verts,
+                    indices: Indices::new(indices, n_verts),
+                },
+            })
+        })
+        .collect();
+
+    GltfMesh {
+        name: mesh.name().map(|s| s.to_string()),
+        primitives,
+    }
+}
+
+/// Returns nodes and root node indices
+fn load_nodes(document: &gltf::Document) -> (Vec<GltfNode>, Vec<usize>) {
+    let mut nodes: Vec<GltfNode> = document
+        .nodes()
+        .map(|node| {
+            let local = Mat4::from_cols_array_2d(&node.transform().matrix());
+            GltfNode {
+                name: node.name().map(|s| s.to_string()),
+                mesh: node.mesh().map(|mesh| mesh.index()),
+                parent: None,
+                children: node.children().map(|child| child.index()).collect(),
+                local,
+                world: local,
+            }
+        })
+        .collect();
+
+    for i in 0..nodes.len() {
+        for child in nodes[i].children.clone() {
+            nodes[child].parent = Some(i);
+        }
+    }
+
+    let roots: Vec<usize> = match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+        None => (0..nodes.len())
+            .filter(|&i| nodes[i].parent.is_none())
+            .collect(),
+    };
+
+    // propagate transforms from the roots (glTF node hierarchy is a forest)
+    let mut visited = vec![false; nodes.len()];
+    let mut stack: Vec<(usize, Mat4)> = roots.iter().map(|&i| (i, Mat4::identity())).collect();
+    while let Some((i, parent)) = stack.pop() {
+        // invalid files may have cycles or nodes with multiple parents
+        if visited[i] {
+            log::warn!(
+                "node {} is visited twice; the node hierarchy is not a forest",
+                i
+            );
+            continue;
+        }
+        visited[i] = true;
+
+        let world = parent * nodes[i].local;
+        nodes[i].world = world;
+        stack.extend(nodes[i].children.iter().map(|&child| (child, world)));
+    }
+
+    (nodes, roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use glam::Vec3;
+
+    use super::*;
+
+    fn load_triangle() -> GltfModel {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/models/triangle.gltf");
+        GltfModel::load(&path).unwrap()
+    }
+
+    #[test]
+    fn mesh() {
+        let model = load_triangle();
+        assert_eq!(model.meshes.len(), 1);
+
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.name.as_deref(), Some("triangle"));
+        assert_eq!(mesh.primitives.len(), 1);
+
+        let prim = &mesh.primitives[0];
+        assert_eq!(prim.material, Some(0));
+        assert_eq!(prim.data.indices, Indices::U16(vec![0, 1, 2]));
+
+        let verts = &prim.data.verts;
+        assert_eq!(verts.len(), 3);
+        assert_eq!(verts[1].pos, [1.0, 0.0, 0.0]);
+        assert_eq!(verts[1].normal, [0.0, 0.0, 1.0]);
+        // flipped vertically
+        assert_eq!(verts[0].uv, [0.0, 1.0]);
+        assert_eq!(verts[2].uv, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn material() {
+        let model = load_triangle();
+        assert_eq!(model.materials.len(), 1);
+
+        let mat = &model.materials[0];
+        assert_eq!(mat.name.as_deref(), Some("red"));
+        assert_eq!(mat.base_color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(mat.metallic, 0.0);
+        assert_eq!(mat.roughness, 0.5);
+        assert!(mat.double_sided);
+        assert_eq!(mat.base_color_tex, None);
+        assert!(model.images.is_empty());
+    }
+
+    #[test]
+    fn node_transforms() {
+        let model = load_triangle();
+        assert_eq!(model.roots, vec![0]);
+
+        let (parent, child) = (&model.nodes[0], &model.nodes[1]);
+        assert_eq!(parent.children, vec![1]);
+        assert_eq!(child.parent, Some(0));
+
+        // scaled by the child, then translated by the parent
+        let p = child.world.transform_point3(Vec3::new(1.0, 1.0, 0.0));
+        assert!((p - Vec3::new(3.0, 2.0, 0.0)).length() < 1e-6);
+
+        let nodes: Vec<_> = model.mesh_nodes().map(|(mesh, _)| mesh).collect();
+        assert_eq!(nodes, vec![&model.meshes[0]]);
+    }
+
+    #[test]
+    fn node_cycle_terminates() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [
+                { "translation": [1.0, 0.0, 0.0], "children": [1] },
+                { "translation": [0.0, 1.0, 0.0], "children": [0] }
+            ]
+        }"#;
+
+        let model = GltfModel::from_slice(json.as_bytes(), None).unwrap();
+        let p = model.nodes[1].world.transform_point3(Vec3::zero());
+        assert!((p - Vec3::new(1.0, 1.0, 0.0)).length() < 1e-6);
+    }
+}
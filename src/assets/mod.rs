@@ -0,0 +1,97 @@
+/*!
+CPU-side asset loaders
+
+Loaders parse files into plain data and never touch the GPU. Call [`MeshData::upload`] to make a
+[`StaticMesh`].
+*/
+
+pub mod gltf;
+pub mod obj;
+
+use crate::gfx::{StaticMesh, Vertex};
+
+crate::vertex! {
+    /// Vertex with position, normal and texture coordinates
+    #[derive(Copy, PartialEq)]
+    pub struct ModelVertex {
+        /// X, Y, Z
+        pub pos: [f32; 3],
+        /// X, Y, Z (zero if the model doesn't have normals)
+        pub normal: [f32; 3],
+        /// u, v (zero if the model doesn't have texture coordinates)
+        pub uv: [f32; 2],
+    }
+}
+
+/// Index buffer data, `u16` if it's enough
+#[derive(Debug, Clone, PartialEq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    /// Chooses `u16` indices if every vertex can be indexed with them
+    pub fn new(indices: Vec<u32>, n_verts: usize) -> Self {
+        if n_verts <= u16::MAX as usize + 1 {
+            Indices::U16(indices.into_iter().map(|i| i as u16).collect())
+        } else {
+            Indices::U32(indices)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::U16(xs) => xs.len(),
+            Indices::U32(xs) => xs.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Index at `i` as `u32`
+    pub fn get(&self, i: usize) -> u32 {
+        match self {
+            Indices::U16(xs) => xs[i] as u32,
+            Indices::U32(xs) => xs[i],
+        }
+    }
+
+    /// Index type to be set to [`rokol::gfx::PipelineDesc`]
+    pub fn index_type(&self) -> rokol::gfx::IndexType {
+        match self {
+            Indices::U16(_) => rokol::gfx::IndexType::UInt16,
+            Indices::U32(_) => rokol::gfx::IndexType::UInt32,
+        }
+    }
+}
+
+/// Indexed vertices on CPU
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshData<V> {
+    pub verts: Vec<V>,
+    pub indices: Indices,
+}
+
+impl<V> MeshData<V> {
+    /// Converts the vertices, e.g. into a vertex type of some shader
+    pub fn map_verts<W>(self, f: impl FnMut(V) -> W) -> MeshData<W> {
+        MeshData {
+            verts: self.verts.into_iter().map(f).collect(),
+            indices: self.indices,
+        }
+    }
+
+    /// Creates GPU buffers. Be sure to use the matching [`Indices::index_type`] in the pipeline
+    pub fn upload(&self) -> StaticMesh<V>
+    where
+        V: Vertex,
+    {
+        match &self.indices {
+            Indices::U16(xs) => StaticMesh::new_16(&self.verts, xs),
+            Indices::U32(xs) => StaticMesh::new_32(&self.verts, xs),
+        }
+    }
+}
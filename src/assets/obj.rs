@@ -0,0 +1,596 @@
+/*!
+Wavefront OBJ + MTL loader
+
+Supported statements:
+
+* OBJ: `v`, `vt`, `vn`, `f` (n-gons are triangulated as fans, negative indices are supported),
+  `o`, `g`, `usemtl` and `mtllib`
+* MTL: `newmtl`, `Ka`, `Kd`, `Ks`, `Ke`, `Ns`, `d`, `Tr`, `map_Kd`, `map_Ks`, `map_Ke`,
+  `map_Bump`, `bump` and `norm`
+
+Other statements are ignored.
+*/
+
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::assets::{Indices, MeshData, ModelVertex};
+
+/// Error on loading OBJ or MTL files
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    /// Malformed statement
+    Parse {
+        /// Name of the file
+        file: String,
+        /// 1-based line number
+        line: usize,
+        msg: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(err) => write!(f, "{}", err),
+            ObjError::Parse { file, line, msg } => write!(f, "{}:{}: {}", file, line, msg),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<io::Error> for ObjError {
+    fn from(err: io::Error) -> Self {
+        ObjError::Io(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ObjError>;
+
+/// Material defined in MTL files
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub emission: [f32; 3],
+    pub shininess: f32,
+    /// 1.0 for opaque
+    pub alpha: f32,
+    /// Paths are relative to the MTL file
+    pub diffuse_map: Option<PathBuf>,
+    pub specular_map: Option<PathBuf>,
+    pub emission_map: Option<PathBuf>,
+    pub normal_map: Option<PathBuf>,
+}
+
+impl ObjMaterial {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ambient: [0.0; 3],
+            diffuse: [1.0; 3],
+            specular: [0.0; 3],
+            emission: [0.0; 3],
+            shininess: 0.0,
+            alpha: 1.0,
+            diffuse_map: None,
+            specular_map: None,
+            emission_map: None,
+            normal_map: None,
+        }
+    }
+}
+
+/// Group of faces sharing one material
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMesh {
+    /// Name of the object or group (`o` or `g`)
+    pub name: String,
+    /// Index in [`ObjModel::materials`]
+    pub material: Option<usize>,
+    pub data: MeshData<ModelVertex>,
+}
+
+/// Parsed OBJ file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+impl ObjModel {
+    /// Loads an OBJ file and MTL files referred to from it
+    pub fn load(path: &Path) -> Result<Self> {
+        let src = fs::read_to_string(path)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let file = path.display().to_string();
+
+        Self::parse_named(&src, &file, |mtl| {
+            let mtl_path = dir.join(mtl);
+            let src = fs::read_to_string(&mtl_path)?;
+            parse_mtl_named(&src, &mtl_path.display().to_string(), dir)
+        })
+    }
+
+    /// Parses OBJ source. `load_mtl` is called with the `mtllib` file names
+    pub fn parse(
+        src: &str,
+        load_mtl: impl FnMut(&str) -> Result<Vec<ObjMaterial>>,
+    ) -> Result<Self> {
+        Self::parse_named(src, "<obj>", load_mtl)
+    }
+
+    fn parse_named(
+        src: &str,
+        file: &str,
+        mut load_mtl: impl FnMut(&str) -> Result<Vec<ObjMaterial>>,
+    ) -> Result<Self> {
+        let mut pos = Vec::<[f32; 3]>::new();
+        let mut uvs = Vec::<[f32; 2]>::new();
+        let mut normals = Vec::<[f32; 3]>::new();
+
+        let mut materials = Vec::<ObjMaterial>::new();
+        let mut meshes = Vec::<ObjMesh>::new();
+        let mut builder = MeshBuilder::new(String::new(), None);
+
+        for (i, line) in src.lines().enumerate() {
+            let err = |msg: String| ObjError::Parse {
+                file: file.to_string(),
+                line: i + 1,
+                msg,
+            };
+
+            let line = self::strip_comment(line);
+            let mut words = line.split_whitespace();
+            let tag = match words.next() {
+                Some(tag) => tag,
+                None => continue,
+            };
+
+            match tag {
+                "v" => pos.push(self::parse_vec3(words.take(3)).map_err(err)?),
+                "vt" => uvs.push(self::parse_uv(words).map_err(err)?),
+                "vn" => normals.push(self::parse_vec3(words).map_err(err)?),
+                "f" => {
+                    let mut face = Vec::with_capacity(4);
+                    for word in words {
+                        let ix =
+                            self::parse_face_vertex(word, [pos.len(), uvs.len(), normals.len()])
+                                .map_err(err)?;
+                        face.push(ix);
+                    }
+                    if face.len() < 3 {
+                        return Err(err(format!("face with {} vertices", face.len())));
+                    }
+                    builder.push_face(&face, &pos, &uvs, &normals);
+                }
+                "o" | "g" => {
+                    let name = words.collect::<Vec<_>>().join(" ");
+                    let material = builder.material;
+                    builder.finish_into(&mut meshes);
+                    builder = MeshBuilder::new(name, material);
+                }
+                "usemtl" => {
+                    let name = words.collect::<Vec<_>>().join(" ");
+                    let material = materials.iter().position(|m| m.name == name);
+                    if material.is_none() {
+                        log::warn!("{}:{}: unknown material `{}`", file, i + 1, name);
+                    }
+                    if material != builder.material {
+                        let name = builder.name.clone();
+                        builder.finish_into(&mut meshes);
+                        builder = MeshBuilder::new(name, material);
+                    }
+                }
+                "mtllib" => {
+                    for mtl in words {
+                        materials.extend(load_mtl(mtl)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        builder.finish_into(&mut meshes);
+
+        Ok(Self { meshes, materials })
+    }
+}
+
+/// Parses MTL source. Texture paths are kept relative
+pub fn parse_mtl(src: &str) -> Result<Vec<ObjMaterial>> {
+    self::parse_mtl_named(src, "<mtl>", Path::new(""))
+}
+
+fn parse_mtl_named(src: &str, file: &str, dir: &Path) -> Result<Vec<ObjMaterial>> {
+    let mut materials = Vec::<ObjMaterial>::new();
+
+    for (i, line) in src.lines().enumerate() {
+        let err = |msg: String| ObjError::Parse {
+            file: file.to_string(),
+            line: i + 1,
+            msg,
+        };
+
+        let line = self::strip_comment(line);
+        let mut words = line.split_whitespace();
+        let tag = match words.next() {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        if tag == "newmtl" {
+            let name = words.collect::<Vec<_>>().join(" ");
+            materials.push(ObjMaterial::new(name));
+            continue;
+        }
+
+        let mat = match materials.last_mut() {
+            Some(mat) => mat,
+            None => return Err(err(format!("`{}` before `newmtl`", tag))),
+        };
+
+        match tag {
+            "Ka" => mat.ambient = self::parse_vec3(words).map_err(err)?,
+            "Kd" => mat.diffuse = self::parse_vec3(words).map_err(err)?,
+            "Ks" => mat.specular = self::parse_vec3(words).map_err(err)?,
+            "Ke" => mat.emission = self::parse_vec3(words).map_err(err)?,
+            "Ns" => mat.shininess = self::parse_f32(words).map_err(err)?,
+            "d" => mat.alpha = self::parse_f32(words).map_err(err)?,
+            "Tr" => mat.alpha = 1.0 - self::parse_f32(words).map_err(err)?,
+            "map_Kd" => mat.diffuse_map = self::parse_map(words, dir),
+            "map_Ks" => mat.specular_map = self::parse_map(words, dir),
+            "map_Ke" => mat.emission_map = self::parse_map(words, dir),
+            "map_Bump" | "map_bump" | "bump" | "norm" => {
+                mat.normal_map = self::parse_map(words, dir)
+            }
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+/// Parses exactly `n` floats
+fn parse_floats<'a>(
+    words: impl Iterator<Item = &'a str>,
+    n: usize,
+) -> std::result::Result<Vec<f32>, String> {
+    let xs = words
+        .map(|word| {
+            word.parse::<f32>()
+                .map_err(|_| format!("invalid number `{}`", word))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    if xs.len() != n {
+        return Err(format!("expected {} numbers, found {}", n, xs.len()));
+    }
+
+    Ok(xs)
+}
+
+fn parse_f32<'a>(words: impl Iterator<Item = &'a str>) -> std::result::Result<f32, String> {
+    Ok(self::parse_floats(words, 1)?[0])
+}
+
+/// Parses `u [v [w]]` of a `vt` statement (`v` defaults to zero and `w` is ignored)
+fn parse_uv<'a>(words: impl Iterator<Item = &'a str>) -> std::result::Result<[f32; 2], String> {
+    let words = words.collect::<Vec<_>>();
+    if !(1..=3).contains(&words.len()) {
+        return Err(format!("expected 1 to 3 numbers, found {}", words.len()));
+    }
+
+    let xs = self::parse_floats(words.iter().copied(), words.len())?;
+    Ok([xs[0], xs.get(1).copied().unwrap_or(0.0)])
+}
+
+fn parse_vec3<'a>(words: impl Iterator<Item = &'a str>) -> std::result::Result<[f32; 3], String> {
+    let xs = self::parse_floats(words, 3)?;
+    Ok([xs[0], xs[1], xs[2]])
+}
+
+/// Takes the last word as the file name (texture options are skipped)
+fn parse_map<'a>(words: impl Iterator<Item = &'a str>, dir: &Path) -> Option<PathBuf> {
+    words.last().map(|name| dir.join(name))
+}
+
+/// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn` into 0-based indices
+///
+/// * `lens`: number of positions, texture coordinates and normals defined so far
+fn parse_face_vertex(
+    word: &str,
+    lens: [usize; 3],
+) -> std::result::Result<[Option<usize>; 3], String> {
+    let mut ix = [None; 3];
+
+    for (i, part) in word.split('/').enumerate() {
+        if i >= 3 {
+            return Err(format!("invalid face vertex `{}`", word));
+        }
+
+        if part.is_empty() {
+            if i == 0 {
+                return Err(format!("face vertex without position `{}`", word));
+            }
+            continue;
+        }
+
+        let x: i64 = part
+            .parse()
+            .map_err(|_| format!("invalid index `{}` in `{}`", part, word))?;
+
+        // 1-based, or relative to the end if negative
+        let resolved = if x > 0 { x - 1 } else { lens[i] as i64 + x };
+
+        if x == 0 || resolved < 0 || resolved >= lens[i] as i64 {
+            return Err(format!("index out of range `{}` in `{}`", part, word));
+        }
+
+        ix[i] = Some(resolved as usize);
+    }
+
+    Ok(ix)
+}
+
+/// Deduplicates face vertices into an indexed mesh
+struct MeshBuilder {
+    name: String,
+    material: Option<usize>,
+    verts: Vec<ModelVertex>,
+    indices: Vec<u32>,
+    cache: HashMap<[Option<usize>; 3], u32>,
+}
+
+impl MeshBuilder {
+    fn new(name: String, material: Option<usize>) -> Self {
+        Self {
+            name,
+            material,
+            verts: Vec::new(),
+            indices: Vec::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    fn push_face(
+        &mut self,
+        face: &[[Option<usize>; 3]],
+        pos: &[[f32; 3]],
+        uvs: &[[f32; 2]],
+        normals: &[[f32; 3]],
+    ) {
+        let mut face_ixs = Vec::with_capacity(face.len());
+
+        for key in face {
+            let verts = &mut self.verts;
+            let ix = *self.cache.entry(*key).or_insert_with(|| {
+                verts.push(ModelVertex {
+                    pos: pos[key[0].unwrap()],
+                    uv: key[1].map(|i| uvs[i]).unwrap_or_default(),
+                    normal: key[2].map(|i| normals[i]).unwrap_or_default(),
+                });
+                verts.len() as u32 - 1
+            });
+            face_ixs.push(ix);
+        }
+
+        // triangle fan
+        for i in 1..face_ixs.len() - 1 {
+            self.indices
+                .extend_from_slice(&[face_ixs[0], face_ixs[i], face_ixs[i + 1]]);
+        }
+    }
+
+    /// Pushes the mesh unless it's empty
+    fn finish_into(self, meshes: &mut Vec<ObjMesh>) {
+        if self.indices.is_empty() {
+            return;
+        }
+
+        let n_verts = self.verts.len();
+        meshes.push(ObjMesh {
+            name: self.name,
+            material: self.material,
+            data: MeshData {
+                verts: self.verts,
+                indices: Indices::new(self.indices, n_verts),
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_mtl(name: &str) -> Result<Vec<ObjMaterial>> {
+        panic!("unexpected mtllib `{}`", name)
+    }
+
+    fn indices(data: &MeshData<ModelVertex>) -> Vec<u32> {
+        (0..data.indices.len())
+            .map(|i| data.indices.get(i))
+            .collect()
+    }
+
+    #[test]
+    fn quad_is_triangulated_as_fan() {
+        let src = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+f 1 2 3 4
+";
+        let model = ObjModel::parse(src, no_mtl).unwrap();
+        assert_eq!(model.meshes.len(), 1);
+
+        let data = &model.meshes[0].data;
+        assert_eq!(data.verts.len(), 4);
+        assert_eq!(indices(data), vec![0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn negative_indices_are_relative_to_the_end() {
+        let src = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+vt 0.5 0.5
+vn 0 0 1
+f -3/-1/-1 -2/-1/-1 -1/-1/-1
+";
+        let model = ObjModel::parse(src, no_mtl).unwrap();
+        let data = &model.meshes[0].data;
+
+        assert_eq!(data.verts[0].pos, [0.0, 0.0, 0.0]);
+        assert_eq!(data.verts[2].pos, [1.0, 1.0, 0.0]);
+        assert!(data
+            .verts
+            .iter()
+            .all(|v| v.uv == [0.5, 0.5] && v.normal == [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn uv_takes_one_to_three_components() {
+        let src = "
+v 0 0 0
+vt 0.25
+vt 0.5 0.75
+vt 0.125 0.375 1
+f 1/1 1/2 1/3
+";
+        let model = ObjModel::parse(src, no_mtl).unwrap();
+        let uvs = model.meshes[0]
+            .data
+            .verts
+            .iter()
+            .map(|v| v.uv)
+            .collect::<Vec<_>>();
+        assert_eq!(uvs, vec![[0.25, 0.0], [0.5, 0.75], [0.125, 0.375]]);
+
+        for src in &["vt\n", "vt 0 0 0 0\n"] {
+            match ObjModel::parse(src, no_mtl) {
+                Err(ObjError::Parse { line, .. }) => assert_eq!(line, 1),
+                x => panic!("expected a parse error, found {:?}", x),
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_index_is_error() {
+        let src = "v 0 0 0\nf 1 2 -2\n";
+        match ObjModel::parse(src, no_mtl) {
+            Err(ObjError::Parse { line, .. }) => assert_eq!(line, 2),
+            x => panic!("expected a parse error, found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn shared_vertices_are_deduplicated() {
+        let src = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+f 1//1 2//1 3//1
+f 1//1 3//1 4//1
+# same position with another normal is another vertex
+vn 0 0 -1
+f 1//2 3//2 2//2
+";
+        let model = ObjModel::parse(src, no_mtl).unwrap();
+        let data = &model.meshes[0].data;
+
+        assert_eq!(data.verts.len(), 4 + 3);
+        assert_eq!(indices(data), vec![0, 1, 2, 0, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn usemtl_splits_meshes() {
+        let mtl = "
+newmtl red
+Kd 1 0 0
+map_Kd red.png
+newmtl blue
+Kd 0 0 1
+";
+        let src = "
+mtllib box.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+o box
+usemtl red
+f 1 2 3
+usemtl blue
+f 3 2 1
+";
+        let model = ObjModel::parse(src, |name| {
+            assert_eq!(name, "box.mtl");
+            parse_mtl(mtl)
+        })
+        .unwrap();
+
+        assert_eq!(model.materials.len(), 2);
+        assert_eq!(model.materials[0].diffuse, [1.0, 0.0, 0.0]);
+        assert_eq!(
+            model.materials[0].diffuse_map,
+            Some(PathBuf::from("red.png"))
+        );
+
+        let meshes: Vec<_> = model
+            .meshes
+            .iter()
+            .map(|m| (m.name.as_str(), m.material))
+            .collect();
+        assert_eq!(meshes, vec![("box", Some(0)), ("box", Some(1))]);
+    }
+
+    #[test]
+    fn unknown_material_is_none() {
+        let src = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+usemtl missing
+f 1 2 3
+";
+        let model = ObjModel::parse(src, no_mtl).unwrap();
+        assert!(model.materials.is_empty());
+        assert_eq!(model.meshes[0].material, None);
+    }
+
+    #[test]
+    fn missing_mtl_file_is_io_error() {
+        let src = "mtllib missing.mtl\n";
+        let res = ObjModel::parse(src, |_| {
+            Err(io::Error::new(io::ErrorKind::NotFound, "missing.mtl").into())
+        });
+        assert!(matches!(res, Err(ObjError::Io(_))));
+    }
+
+    #[test]
+    fn mtl_statement_before_newmtl_is_error() {
+        assert!(matches!(
+            parse_mtl("Kd 1 1 1\n"),
+            Err(ObjError::Parse { line: 1, .. })
+        ));
+    }
+}
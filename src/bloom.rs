@@ -0,0 +1,271 @@
+/*!
+Bloom: blurred bright part of the scene added in the tone mapping pass
+
+Scene shaders write the bright part into the second attachment of [`Hdr::scene_pass`] (see
+`glsl/hdr.fs.glsl`). [`Bloom`] blurs it with a separable Gaussian blur at a lower resolution. The
+`blur` shader is taken from the [`ShaderCache`] of [`Hdr`], so toggling bloom doesn't recompile it:
+
+```ignore
+let mut hdr = Hdr::new(w, h);
+hdr.set_bloom(Some(Bloom::new(w, h)));
+```
+
+[`Hdr`]: crate::hdr::Hdr
+[`Hdr::scene_pass`]: crate::hdr::Hdr::scene_pass
+*/
+
+use rokol::gfx as rg;
+
+use crate::{
+    gfx::{RenderTexture2d, StaticMesh},
+    hdr::HDR_FORMAT,
+    shaders::{BlurUniforms, PostVertex, ShaderCache},
+};
+
+/// Maximum radius of the blur kernel (`blur.fs` has 16 weights)
+pub const MAX_RADIUS: usize = 15;
+
+/// Weights of a 1D Gaussian kernel from the center texel to `radius`
+///
+/// The weights are normalized so that the whole kernel (`2 * radius + 1` texels) sums to one.
+/// `radius` is clamped to [`MAX_RADIUS`].
+pub fn gaussian_weights(sigma: f32, radius: usize) -> Vec<f32> {
+    let radius = radius.min(MAX_RADIUS);
+    let sigma = sigma.max(1e-3);
+
+    let mut weights: Vec<f32> = (0..=radius)
+        .map(|i| {
+            let x = i as f32;
+            (-(x * x) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+
+    // the center is sampled once, the others twice
+    let sum = weights[0] + 2.0 * weights[1..].iter().sum::<f32>();
+    for w in &mut weights {
+        *w /= sum;
+    }
+
+    weights
+}
+
+/// Packs weights into `vec4 weights[4]` of `blur.fs` (the rest is filled with zeros)
+pub fn pack_weights(weights: &[f32]) -> [[f32; 4]; 4] {
+    let mut packed = [[0.0; 4]; 4];
+    for (i, w) in weights.iter().take(MAX_RADIUS + 1).enumerate() {
+        packed[i / 4][i % 4] = *w;
+    }
+    packed
+}
+
+/// Size of the blur targets for the screen size (at least one pixel)
+pub fn scaled_size(w: u32, h: u32, scale: f32) -> [u32; 2] {
+    let f = |x: u32| ((x as f32 * scale).round() as u32).max(1);
+    [f(w), f(h)]
+}
+
+/// Ping-pong Gaussian blur of the bright part of the scene
+#[derive(Debug)]
+pub struct Bloom {
+    /// Horizontal pass writes to the first one and vertical pass to the second one
+    targets: [RenderTexture2d; 2],
+    /// From the center texel
+    weights: Vec<f32>,
+    sigma: f32,
+    /// Number of horizontal + vertical blur pairs
+    passes: usize,
+    /// Resolution of the blur relative to the screen
+    scale: f32,
+    strength: f32,
+    screen_size: [u32; 2],
+    pa: rg::PassAction,
+}
+
+impl Bloom {
+    /// Blur at half resolution with sigma 4, radius 8 and 5 passes
+    pub fn new(w: u32, h: u32) -> Self {
+        let scale = 0.5;
+        let [bw, bh] = self::scaled_size(w, h, scale);
+
+        Self {
+            targets: [self::blur_target(bw, bh), self::blur_target(bw, bh)],
+            weights: self::gaussian_weights(4.0, 8),
+            sigma: 4.0,
+            passes: 5,
+            scale,
+            strength: 1.0,
+            screen_size: [w, h],
+            pa: rg::PassAction::clear([0.0, 0.0, 0.0, 1.0]),
+        }
+    }
+
+    pub fn sigma(&self) -> f32 {
+        self.sigma
+    }
+
+    /// Number of texels sampled on each side
+    pub fn radius(&self) -> usize {
+        self.weights.len() - 1
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    /// Regenerates the kernel. `radius` is clamped to [`MAX_RADIUS`]
+    pub fn set_kernel(&mut self, sigma: f32, radius: usize) {
+        self.sigma = sigma;
+        self.weights = self::gaussian_weights(sigma, radius);
+    }
+
+    pub fn passes(&self) -> usize {
+        self.passes
+    }
+
+    /// Sets the number of horizontal + vertical blur pairs
+    pub fn set_passes(&mut self, passes: usize) {
+        self.passes = passes;
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Sets the resolution of the blur relative to the screen (recreates the targets on change)
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(1e-3, 1.0);
+        let [w, h] = self.screen_size;
+        self.resize_targets(w, h);
+    }
+
+    pub fn strength(&self) -> f32 {
+        self.strength
+    }
+
+    /// Sets the scale of the bloom added to the scene
+    pub fn set_strength(&mut self, strength: f32) {
+        self.strength = strength.max(0.0);
+    }
+
+    /// Size of the blur targets
+    pub fn size(&self) -> [u32; 2] {
+        self.targets[0].size()
+    }
+
+    /// Recreates the blur targets for the new screen size
+    pub fn resize(&mut self, w: u32, h: u32) {
+        self.screen_size = [w, h];
+        self.resize_targets(w, h);
+    }
+
+    fn resize_targets(&mut self, w: u32, h: u32) {
+        let [bw, bh] = self::scaled_size(w, h, self.scale);
+        for target in &mut self.targets {
+            target.resize(bw, bh);
+        }
+    }
+
+    /// Blurs `src` of `src_size` with the `blur` shader of `shaders` and returns the blurred
+    /// image. Returns `src` if there's no pass
+    pub fn blur(
+        &mut self,
+        shaders: &mut ShaderCache,
+        tri: &mut StaticMesh<PostVertex>,
+        src: rg::Image,
+        src_size: [u32; 2],
+    ) -> rg::Image {
+        let mut uniforms = BlurUniforms {
+            weights: self::pack_weights(&self.weights),
+            dir: [0.0, 0.0],
+            radius: self.radius() as f32,
+        };
+
+        let shd = shaders.get("blur", &[]);
+        let mut img = src;
+        let mut img_size = src_size;
+
+        for _ in 0..self.passes {
+            // horizontal, then vertical
+            for (i, target) in self.targets.iter().enumerate() {
+                uniforms.dir = if i == 0 {
+                    [1.0 / img_size[0] as f32, 0.0]
+                } else {
+                    [0.0, 1.0 / img_size[1] as f32]
+                };
+
+                rg::begin_pass(target.pass(), &self.pa);
+                shd.apply_pip();
+                tri.bind_img(img, 0);
+                shd.apply_fs(&uniforms);
+                tri.draw_all();
+                rg::end_pass();
+
+                img = target.img();
+                img_size = target.size();
+            }
+        }
+
+        img
+    }
+}
+
+fn blur_target(w: u32, h: u32) -> RenderTexture2d {
+    RenderTexture2d::builder(w, h)
+        .format(HDR_FORMAT)
+        .depth_stencil(false)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sum of the whole kernel (the center is sampled once, the others twice)
+    fn kernel_sum(weights: &[f32]) -> f32 {
+        weights[0] + 2.0 * weights[1..].iter().sum::<f32>()
+    }
+
+    #[test]
+    fn weights_are_normalized() {
+        for &(sigma, radius) in &[(1.0, 3), (4.0, 8), (10.0, 15), (0.0, 4)] {
+            let weights = self::gaussian_weights(sigma, radius);
+            assert_eq!(weights.len(), radius + 1);
+            assert!((self::kernel_sum(&weights) - 1.0).abs() < 1e-5);
+            // decreasing from the center
+            assert!(weights.windows(2).all(|w| w[0] >= w[1]));
+        }
+    }
+
+    #[test]
+    fn radius_is_clamped() {
+        let weights = self::gaussian_weights(4.0, 100);
+        assert_eq!(weights.len(), MAX_RADIUS + 1);
+        assert!((self::kernel_sum(&weights) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn radius_zero_is_identity() {
+        assert_eq!(self::gaussian_weights(4.0, 0), vec![1.0]);
+    }
+
+    #[test]
+    fn packing_order() {
+        let weights: Vec<f32> = (0..6).map(|i| i as f32).collect();
+        let packed = self::pack_weights(&weights);
+        assert_eq!(packed[0], [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(packed[1], [4.0, 5.0, 0.0, 0.0]);
+        assert_eq!(packed[2], [0.0; 4]);
+        assert_eq!(packed[3], [0.0; 4]);
+
+        // extra weights are dropped
+        let weights: Vec<f32> = (0..20).map(|i| i as f32).collect();
+        let packed = self::pack_weights(&weights);
+        assert_eq!(packed[3], [12.0, 13.0, 14.0, 15.0]);
+    }
+
+    #[test]
+    fn scaled_sizes() {
+        assert_eq!(self::scaled_size(1280, 720, 0.5), [640, 360]);
+        assert_eq!(self::scaled_size(3, 1, 0.25), [1, 1]);
+    }
+}
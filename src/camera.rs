@@ -0,0 +1,328 @@
+/*!
+Cameras with right-handed view matrices and OpenGL projection matrices
+
+Matrices are column-major (v' = Mv), so `view_proj` is `proj * view`.
+*/
+
+use glam::{Mat4, Vec3};
+
+/// Maximum pitch in radians, just under 90 degrees to avoid gimbal flip
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+/// View + projection
+pub trait Camera {
+    /// World to view matrix
+    fn view(&self) -> Mat4;
+
+    /// View to clip matrix
+    fn proj(&self) -> Mat4;
+
+    fn view_proj(&self) -> Mat4 {
+        self.proj() * self.view()
+    }
+}
+
+/// Projection matrix parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectionKind {
+    Perspective {
+        /// Vertical field of view in radians
+        fov_y: f32,
+    },
+    Orthographic {
+        /// Height of the view volume (width is computed with the aspect ratio)
+        height: f32,
+    },
+}
+
+/// Projection matrix with aspect ratio (width / height)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projection {
+    pub kind: ProjectionKind,
+    pub aspect: f32,
+    pub z_near: f32,
+    pub z_far: f32,
+}
+
+impl Projection {
+    pub fn perspective(fov_y: f32, aspect: f32) -> Self {
+        Self {
+            kind: ProjectionKind::Perspective { fov_y },
+            aspect,
+            z_near: 0.01,
+            z_far: 100.0,
+        }
+    }
+
+    pub fn orthographic(height: f32, aspect: f32) -> Self {
+        Self {
+            kind: ProjectionKind::Orthographic { height },
+            aspect,
+            z_near: -100.0,
+            z_far: 100.0,
+        }
+    }
+
+    /// Sets aspect ratio from the framebuffer size
+    pub fn set_size(&mut self, w: u32, h: u32) {
+        // avoid NaN on minimized window
+        if h > 0 {
+            self.aspect = w as f32 / h as f32;
+        }
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        // `_gl` variants map depth to [-1, 1]
+        match self.kind {
+            ProjectionKind::Perspective { fov_y } => {
+                Mat4::perspective_rh_gl(fov_y, self.aspect, self.z_near, self.z_far)
+            }
+            ProjectionKind::Orthographic { height } => {
+                let (hw, hh) = (height * self.aspect / 2.0, height / 2.0);
+                Mat4::orthographic_rh_gl(-hw, hw, -hh, hh, self.z_near, self.z_far)
+            }
+        }
+    }
+}
+
+/// Unit vector from yaw and pitch (yaw zero looks at -Z, as in the Learn OpenGL camera)
+pub fn dir_from_angles(yaw: f32, pitch: f32) -> Vec3 {
+    Vec3::new(
+        pitch.cos() * yaw.sin(),
+        pitch.sin(),
+        -pitch.cos() * yaw.cos(),
+    )
+}
+
+/// Yaw and pitch facing `offset` (pitch is clamped), or `None` if `offset` is (almost) zero
+fn angles_to(offset: Vec3) -> Option<(f32, f32)> {
+    if offset.length_squared() <= f32::EPSILON {
+        return None;
+    }
+
+    let dir = offset.normalize();
+    Some((
+        dir.x.atan2(-dir.z),
+        dir.y.asin().clamp(-MAX_PITCH, MAX_PITCH),
+    ))
+}
+
+/// FPS-style camera
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlyCamera {
+    pub pos: Vec3,
+    /// Rotation around the Y axis in radians
+    pub yaw: f32,
+    /// Rotation from the XZ plane in radians
+    pub pitch: f32,
+    /// World units per second
+    pub speed: f32,
+    /// Radians per mouse movement pixel
+    pub sensitivity: f32,
+    pub proj: Projection,
+}
+
+impl FlyCamera {
+    pub fn new(pos: Vec3, proj: Projection) -> Self {
+        Self {
+            pos,
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 2.5,
+            sensitivity: 0.002,
+            proj,
+        }
+    }
+
+    /// Sets yaw and pitch so that the camera faces `target`. Keeps the orientation if `target`
+    /// is at the camera position
+    pub fn look_at(&mut self, target: Vec3) {
+        if let Some((yaw, pitch)) = self::angles_to(target - self.pos) {
+            self.yaw = yaw;
+            self.pitch = pitch;
+        }
+    }
+
+    pub fn front(&self) -> Vec3 {
+        self::dir_from_angles(self.yaw, self.pitch)
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.front().cross(Vec3::unit_y()).normalize()
+    }
+
+    /// Rotates the camera with mouse movement
+    pub fn rotate(&mut self, dx: f32, dy: f32) {
+        self.yaw += dx * self.sensitivity;
+        // screen Y axis goes down
+        self.pitch = (self.pitch - dy * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Moves the camera relative to its orientation
+    ///
+    /// * `dir`: (right, up, forward), each component in [-1, 1]
+    /// * `dt`: delta time in seconds
+    pub fn translate(&mut self, dir: Vec3, dt: f32) {
+        let delta = self.right() * dir.x + Vec3::unit_y() * dir.y + self.front() * dir.z;
+        self.pos += delta * self.speed * dt;
+    }
+}
+
+impl Camera for FlyCamera {
+    fn view(&self) -> Mat4 {
+        Mat4::look_at_rh(self.pos, self.pos + self.front(), Vec3::unit_y())
+    }
+
+    fn proj(&self) -> Mat4 {
+        self.proj.matrix()
+    }
+}
+
+/// Camera rotating around a target point
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitCamera {
+    pub target: Vec3,
+    pub distance: f32,
+    /// Rotation around the Y axis in radians
+    pub yaw: f32,
+    /// Rotation from the XZ plane in radians
+    pub pitch: f32,
+    /// Radians per mouse movement pixel
+    pub sensitivity: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub proj: Projection,
+}
+
+impl OrbitCamera {
+    pub fn new(target: Vec3, distance: f32, proj: Projection) -> Self {
+        Self {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            sensitivity: 0.005,
+            min_distance: 0.1,
+            max_distance: 1000.0,
+            proj,
+        }
+    }
+
+    /// Camera position
+    pub fn pos(&self) -> Vec3 {
+        // the camera sits on the opposite side of the looking direction
+        self.target - self::dir_from_angles(self.yaw, self.pitch) * self.distance
+    }
+
+    /// Places the camera at `pos` keeping the target. Does nothing if `pos` is the target
+    pub fn set_pos(&mut self, pos: Vec3) {
+        let offset = self.target - pos;
+        if let Some((yaw, pitch)) = self::angles_to(offset) {
+            self.distance = offset.length();
+            self.yaw = yaw;
+            self.pitch = pitch;
+        }
+    }
+
+    /// Rotates the camera around the target with mouse movement
+    pub fn rotate(&mut self, dx: f32, dy: f32) {
+        self.yaw += dx * self.sensitivity;
+        self.pitch = (self.pitch - dy * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Zooms in with positive `delta` (e.g. mouse scroll)
+    pub fn zoom(&mut self, delta: f32) {
+        self.distance =
+            (self.distance * (1.0 - delta * 0.1)).clamp(self.min_distance, self.max_distance);
+    }
+}
+
+impl Camera for OrbitCamera {
+    fn view(&self) -> Mat4 {
+        Mat4::look_at_rh(self.pos(), self.target, Vec3::unit_y())
+    }
+
+    fn proj(&self) -> Mat4 {
+        self.proj.matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{} != {}", a, b);
+    }
+
+    fn proj() -> Projection {
+        Projection::perspective(std::f32::consts::FRAC_PI_3, 16.0 / 9.0)
+    }
+
+    #[test]
+    fn zero_angles_look_at_negative_z() {
+        assert_near(self::dir_from_angles(0.0, 0.0), -Vec3::unit_z());
+    }
+
+    #[test]
+    fn fly_look_at() {
+        let mut cam = FlyCamera::new(Vec3::new(1.0, 2.0, 3.0), proj());
+        cam.look_at(Vec3::new(4.0, 2.0, -1.0));
+        assert_near(cam.front(), Vec3::new(3.0, 0.0, -4.0) / 5.0);
+        assert_near(cam.right(), Vec3::new(4.0, 0.0, 3.0) / 5.0);
+    }
+
+    #[test]
+    fn fly_look_at_clamps_pitch() {
+        let mut cam = FlyCamera::new(Vec3::zero(), proj());
+        cam.look_at(Vec3::unit_y());
+        assert_eq!(cam.pitch, MAX_PITCH);
+        assert!(cam.view().is_finite());
+    }
+
+    #[test]
+    fn fly_look_at_own_pos_keeps_orientation() {
+        let mut cam = FlyCamera::new(Vec3::one(), proj());
+        cam.yaw = 0.5;
+        cam.pitch = 0.25;
+        cam.look_at(Vec3::one());
+        assert_eq!((cam.yaw, cam.pitch), (0.5, 0.25));
+        assert!(cam.view().is_finite());
+    }
+
+    #[test]
+    fn orbit_set_pos() {
+        let mut cam = OrbitCamera::new(Vec3::new(0.0, 1.0, 0.0), 1.0, proj());
+        let pos = Vec3::new(4.0, 3.0, 8.0);
+        cam.set_pos(pos);
+        assert_near(cam.pos(), pos);
+        assert!((cam.distance - (pos - cam.target).length()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn orbit_set_pos_to_target_keeps_orientation() {
+        let mut cam = OrbitCamera::new(Vec3::zero(), 2.0, proj());
+        cam.yaw = 0.5;
+        cam.set_pos(Vec3::zero());
+        assert_eq!((cam.distance, cam.yaw, cam.pitch), (2.0, 0.5, 0.0));
+        assert!(cam.view().is_finite());
+    }
+
+    #[test]
+    fn orbit_zoom_is_clamped() {
+        let mut cam = OrbitCamera::new(Vec3::zero(), 1.0, proj());
+        cam.zoom(100.0);
+        assert_eq!(cam.distance, cam.min_distance);
+        cam.zoom(-1e6);
+        assert_eq!(cam.distance, cam.max_distance);
+    }
+
+    #[test]
+    fn set_size_ignores_zero_height() {
+        let mut proj = proj();
+        proj.set_size(800, 400);
+        assert_eq!(proj.aspect, 2.0);
+        proj.set_size(800, 0);
+        assert_eq!(proj.aspect, 2.0);
+    }
+}
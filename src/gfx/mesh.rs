@@ -3,6 +3,8 @@ use {
     std::marker::PhantomData,
 };
 
+use crate::gfx::Vertex;
+
 /// Immutable buffers
 #[derive(Debug, Clone, Default)]
 pub struct StaticMesh<V> {
@@ -18,7 +20,7 @@ impl<V> Drop for StaticMesh<V> {
     }
 }
 
-impl<V> StaticMesh<V> {
+impl<V: Vertex> StaticMesh<V> {
     fn new<I>(verts: &[V], indices: &[I]) -> Self {
         Self {
             bind: rg::Bindings {
@@ -44,7 +46,9 @@ impl<V> StaticMesh<V> {
     pub fn new_32(verts: &[V], indices: &[u32]) -> Self {
         Self::new(verts, indices)
     }
+}
 
+impl<V> StaticMesh<V> {
     /// slot: [0, 12)
     pub fn bind_img(&mut self, img: rg::Image, slot: usize) {
         self.bind.fs_images[slot] = img;
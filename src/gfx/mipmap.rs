@@ -0,0 +1,295 @@
+/*!
+CPU mipmap generation for RGBA8 images
+
+Levels are downsampled with 16 bits per channel. sRGB images are downsampled in linear space
+([`gen_srgb_mip_chain`]).
+*/
+
+use image::{imageops, ImageBuffer, Rgba};
+
+/// Maximum number of mipmap levels of an image (`SG_MAX_MIPMAPS`)
+pub const MAX_MIPMAPS: usize = 16;
+
+/// Downsampling filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MipFilter {
+    /// Average of 2x2 texels of the previous level (fast)
+    Box,
+    /// Lanczos resampling of the base level (sharper)
+    Lanczos,
+}
+
+/// Number of levels of a full mip chain down to 1x1 (capped at [`MAX_MIPMAPS`])
+pub fn level_count(w: u32, h: u32) -> usize {
+    let max = w.max(h).max(1);
+    let n = 32 - max.leading_zeros() as usize;
+    n.min(MAX_MIPMAPS)
+}
+
+/// Size of the mip level `level`
+pub fn level_size(w: u32, h: u32, level: usize) -> [u32; 2] {
+    [(w >> level).max(1), (h >> level).max(1)]
+}
+
+/// Generates the full mip chain of an RGBA8 image. The first level is the base image
+pub fn gen_mip_chain(pixels: &[u8], w: u32, h: u32, filter: MipFilter) -> Vec<Vec<u8>> {
+    self::check_len(pixels, w, h);
+
+    let base = pixels.iter().map(|x| *x as u16 * 257).collect();
+    self::gen_levels(pixels, base, w, h, filter, |texels| {
+        texels.iter().map(|x| self::to_u8(*x)).collect()
+    })
+}
+
+/// Generates the full mip chain of an sRGB RGBA8 image. The first level is the base image
+///
+/// RGB is decoded to linear before downsampling and encoded back (alpha is linear), so that
+/// averaging doesn't darken the smaller levels.
+pub fn gen_srgb_mip_chain(pixels: &[u8], w: u32, h: u32, filter: MipFilter) -> Vec<Vec<u8>> {
+    self::check_len(pixels, w, h);
+
+    let decode: Vec<u16> = (0..=255u8)
+        .map(|x| (self::srgb_to_linear(x as f32 / 255.0) * 65535.0).round() as u16)
+        .collect();
+
+    let mut base = Vec::with_capacity(pixels.len());
+    for px in pixels.chunks(4) {
+        base.extend(px[0..3].iter().map(|x| decode[*x as usize]));
+        base.push(px[3] as u16 * 257);
+    }
+
+    let encode = |x: u16| {
+        let srgb = self::linear_to_srgb(x as f32 / 65535.0);
+        (srgb * 255.0).round() as u8
+    };
+
+    self::gen_levels(pixels, base, w, h, filter, |texels| {
+        let mut out = Vec::with_capacity(texels.len());
+        for px in texels.chunks(4) {
+            out.extend(px[0..3].iter().map(|x| encode(*x)));
+            out.push(self::to_u8(px[3]));
+        }
+        out
+    })
+}
+
+fn check_len(pixels: &[u8], w: u32, h: u32) {
+    assert_eq!(
+        pixels.len(),
+        (w * h * 4) as usize,
+        "pixel buffer doesn't match {}x{} RGBA8",
+        w,
+        h
+    );
+}
+
+/// Downsamples `base` (16-bit RGBA) and converts the levels back to RGBA8 with `to_rgba8`. The
+/// first level is a copy of `pixels`
+fn gen_levels(
+    pixels: &[u8],
+    base: Vec<u16>,
+    w: u32,
+    h: u32,
+    filter: MipFilter,
+    to_rgba8: impl Fn(&[u16]) -> Vec<u8>,
+) -> Vec<Vec<u8>> {
+    let n = self::level_count(w, h);
+    let mut levels = Vec::with_capacity(n);
+    levels.push(pixels.to_vec());
+
+    // every level is resampled from the base level with Lanczos
+    let lanczos_base = match filter {
+        MipFilter::Box => None,
+        // `base` has the right length
+        MipFilter::Lanczos => {
+            Some(ImageBuffer::<Rgba<u16>, _>::from_raw(w, h, base.clone()).unwrap())
+        }
+    };
+
+    let mut prev = base;
+    for level in 1..n {
+        let [lw, lh] = self::level_size(w, h, level);
+        let next = match lanczos_base {
+            Some(ref base) => {
+                imageops::resize(base, lw, lh, imageops::FilterType::Lanczos3).into_raw()
+            }
+            None => {
+                let [pw, ph] = self::level_size(w, h, level - 1);
+                self::box_downsample(&prev, pw, ph, lw, lh)
+            }
+        };
+        levels.push(to_rgba8(&next));
+        prev = next;
+    }
+
+    levels
+}
+
+fn to_u8(x: u16) -> u8 {
+    ((x as u32 + 128) / 257) as u8
+}
+
+/// sRGB transfer function (`[0, 1]`)
+pub fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Inverse of [`srgb_to_linear`]
+pub fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.003_130_8 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Averages 2x2 texels of 16-bit RGBA (clamped on odd edges)
+fn box_downsample(src: &[u16], sw: u32, sh: u32, dw: u32, dh: u32) -> Vec<u16> {
+    let mut dst = Vec::with_capacity((dw * dh * 4) as usize);
+    let texel = |x: u32, y: u32, c: usize| src[((y * sw + x) * 4) as usize + c] as u32;
+
+    for y in 0..dh {
+        let (y0, y1) = ((y * 2).min(sh - 1), (y * 2 + 1).min(sh - 1));
+        for x in 0..dw {
+            let (x0, x1) = ((x * 2).min(sw - 1), (x * 2 + 1).min(sw - 1));
+            for c in 0..4 {
+                let sum = texel(x0, y0, c) + texel(x1, y0, c) + texel(x0, y1, c) + texel(x1, y1, c);
+                // round to nearest
+                dst.push(((sum + 2) / 4) as u16);
+            }
+        }
+    }
+
+    dst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `w`x`h` RGBA8 image of a single color
+    fn solid(w: u32, h: u32, color: [u8; 4]) -> Vec<u8> {
+        color
+            .iter()
+            .cycle()
+            .take((w * h * 4) as usize)
+            .cloned()
+            .collect()
+    }
+
+    fn sizes(levels: &[Vec<u8>]) -> Vec<usize> {
+        levels.iter().map(|l| l.len() / 4).collect()
+    }
+
+    #[test]
+    fn level_counts() {
+        assert_eq!(self::level_count(1, 1), 1);
+        assert_eq!(self::level_count(0, 0), 1);
+        assert_eq!(self::level_count(2, 2), 2);
+        assert_eq!(self::level_count(256, 256), 9);
+        assert_eq!(self::level_count(1, 8), 4);
+        assert_eq!(self::level_count(640, 480), 10);
+        assert_eq!(self::level_count(1 << 20, 1), MAX_MIPMAPS);
+    }
+
+    #[test]
+    fn level_sizes() {
+        assert_eq!(self::level_size(256, 64, 0), [256, 64]);
+        assert_eq!(self::level_size(256, 64, 3), [32, 8]);
+        assert_eq!(self::level_size(256, 64, 8), [1, 1]);
+        // non-power-of-two sizes round down
+        assert_eq!(self::level_size(5, 3, 1), [2, 1]);
+        assert_eq!(self::level_size(5, 3, 2), [1, 1]);
+        assert_eq!(self::level_size(1, 8, 2), [1, 2]);
+    }
+
+    #[test]
+    fn box_average() {
+        #[rustfmt::skip]
+        let pixels = [
+            0, 0, 0, 255,     255, 0, 0, 255,
+            0, 255, 0, 255,   0, 0, 255, 255,
+        ];
+        let levels = self::gen_mip_chain(&pixels, 2, 2, MipFilter::Box);
+
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0], pixels.to_vec());
+        // (255 + 2) / 4 = 64
+        assert_eq!(levels[1], vec![64, 64, 64, 255]);
+    }
+
+    #[test]
+    fn chain_sizes() {
+        for &filter in &[MipFilter::Box, MipFilter::Lanczos] {
+            let levels = self::gen_mip_chain(&self::solid(1, 8, [0; 4]), 1, 8, filter);
+            assert_eq!(self::sizes(&levels), vec![8, 4, 2, 1]);
+
+            let levels = self::gen_mip_chain(&self::solid(5, 3, [0; 4]), 5, 3, filter);
+            assert_eq!(self::sizes(&levels), vec![15, 2, 1]);
+
+            let levels = self::gen_mip_chain(&self::solid(1, 1, [0; 4]), 1, 1, filter);
+            assert_eq!(self::sizes(&levels), vec![1]);
+        }
+    }
+
+    #[test]
+    fn solid_color_is_kept() {
+        let color = [10, 100, 200, 255];
+        for &filter in &[MipFilter::Box, MipFilter::Lanczos] {
+            let levels = self::gen_mip_chain(&self::solid(6, 3, color), 6, 3, filter);
+            for (i, level) in levels.iter().enumerate() {
+                let [w, h] = self::level_size(6, 3, i);
+                assert_eq!(level, &self::solid(w, h, color), "{:?} level {}", filter, i);
+            }
+        }
+    }
+
+    #[test]
+    fn srgb_round_trip() {
+        for i in 0..=255u8 {
+            let x = i as f32 / 255.0;
+            let y = self::linear_to_srgb(self::srgb_to_linear(x));
+            assert!((x - y).abs() < 1e-4, "{} -> {}", x, y);
+        }
+    }
+
+    #[test]
+    fn srgb_average_is_linear() {
+        // black and white
+        #[rustfmt::skip]
+        let pixels = [
+            0, 0, 0, 0,         255, 255, 255, 255,
+            255, 255, 255, 255, 0, 0, 0, 0,
+        ];
+
+        let linear = self::gen_mip_chain(&pixels, 2, 2, MipFilter::Box);
+        assert_eq!(linear[1], vec![128, 128, 128, 128]);
+
+        // half intensity is ~188 in sRGB, alpha stays linear
+        let srgb = self::gen_srgb_mip_chain(&pixels, 2, 2, MipFilter::Box);
+        assert_eq!(srgb[0], pixels.to_vec());
+        assert_eq!(srgb[1], vec![188, 188, 188, 128]);
+    }
+
+    #[test]
+    fn srgb_solid_color_is_kept() {
+        let color = [10, 100, 200, 255];
+        for &filter in &[MipFilter::Box, MipFilter::Lanczos] {
+            let levels = self::gen_srgb_mip_chain(&self::solid(4, 4, color), 4, 4, filter);
+            assert_eq!(levels.len(), 3);
+            for level in &levels[1..] {
+                assert!(level.chunks(4).all(|px| px == color), "{:?}", filter);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_buffer_size() {
+        self::gen_mip_chain(&[0; 12], 2, 2, MipFilter::Box);
+    }
+}
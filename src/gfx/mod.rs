@@ -3,9 +3,21 @@ RAII graphics objects on [`rokol::gfx`]
 */
 
 mod mesh;
+pub mod mipmap;
 mod shader;
 mod tex;
+mod uniform;
+mod vertex;
 
 pub use mesh::{DynamicMesh, StaticMesh};
-pub use shader::Shader;
-pub use tex::{RenderTexture2d, Texture2dDrop, TextureBuilder};
+pub use mipmap::MipFilter;
+pub use shader::{HotReload, Shader, ShaderBuilder, ShaderLoader};
+pub use tex::{
+    bytes_per_pixel, check_buffer_size, CubemapBuilder, MultiRenderTarget, RenderTexture2d,
+    RenderTextureBuilder, Texture2dDrop, TextureBuilder, TextureCube, TextureError,
+};
+pub use uniform::{
+    check_layout, packed_size, std140_layout, uniform_block_desc, Std140Layout, UniformBlock,
+    UniformField, UniformMember,
+};
+pub use vertex::{Vertex, VertexAttr, VertexField};
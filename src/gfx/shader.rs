@@ -2,13 +2,110 @@
 TODO: maybe recommend bytemuck for `as_bytes`
 */
 
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
 use rokol::gfx::{self as rg, BakedResource};
 
+use crate::gfx::UniformBlock;
+
+/// Creates [`rg::Shader`] and [`rg::Pipeline`] from null-terminated vertex and fragment shader
+/// sources
+pub type ShaderBuilder = Box<dyn Fn(&str, &str) -> (rg::Shader, rg::Pipeline)>;
+
+/// Loads null-terminated vertex and fragment shader sources, along with the files to watch
+pub type ShaderLoader = Box<dyn Fn() -> Result<([String; 2], Vec<PathBuf>), String>>;
+
+/// Interval to poll shader files
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches modification time of files
+#[derive(Debug)]
+struct FileWatch {
+    paths: Vec<PathBuf>,
+    mtimes: Vec<Option<SystemTime>>,
+    last_poll: Instant,
+}
+
+impl FileWatch {
+    fn new(paths: Vec<PathBuf>) -> Self {
+        let mtimes = paths.iter().map(|p| self::mtime(p)).collect();
+        Self {
+            paths,
+            mtimes,
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Returns true if any of the files is modified since the last check, at most once per
+    /// [`POLL_INTERVAL`]
+    fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        self.check()
+    }
+
+    /// Returns true if any of the files is modified since the last check
+    fn check(&mut self) -> bool {
+        let mtimes: Vec<_> = self.paths.iter().map(|p| self::mtime(p)).collect();
+        if mtimes == self.mtimes {
+            return false;
+        }
+
+        self.mtimes = mtimes;
+        true
+    }
+
+    /// Replaces the watched files (e.g. when the set of included files changes)
+    fn set_paths(&mut self, paths: Vec<PathBuf>) {
+        if paths != self.paths {
+            self.mtimes = paths.iter().map(|p| self::mtime(p)).collect();
+            self.paths = paths;
+        }
+    }
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Watches modification time of shader files
+pub struct HotReload {
+    /// Source files including the ones included from the shaders
+    watch: FileWatch,
+    load: ShaderLoader,
+    build: ShaderBuilder,
+}
+
+impl std::fmt::Debug for HotReload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HotReload")
+            .field("watch", &self.watch)
+            .finish()
+    }
+}
+
+impl HotReload {
+    pub fn new(paths: Vec<PathBuf>, load: ShaderLoader, build: ShaderBuilder) -> Self {
+        Self {
+            watch: FileWatch::new(paths),
+            load,
+            build,
+        }
+    }
+}
+
 /// [`rg::Shader`] + [`rg::Pipeline`] with methods
 #[derive(Debug)]
 pub struct Shader {
     shd: rg::Shader,
     pip: rg::Pipeline,
+    reload: Option<HotReload>,
 }
 
 impl std::ops::Drop for Shader {
@@ -20,7 +117,66 @@ impl std::ops::Drop for Shader {
 
 impl Shader {
     pub fn new(shd: rg::Shader, pip: rg::Pipeline) -> Self {
-        Self { shd, pip }
+        Self {
+            shd,
+            pip,
+            reload: None,
+        }
+    }
+
+    /// Enables [`Shader::hot_reload`]
+    pub fn with_hot_reload(mut self, reload: HotReload) -> Self {
+        self.reload = Some(reload);
+        self
+    }
+
+    /// Recreates the shader and the pipeline if the source files are modified. Returns true if
+    /// it's reloaded
+    ///
+    /// If the new source fails to compile, the old shader is kept.
+    pub fn hot_reload(&mut self) -> bool {
+        let reload = match self.reload.as_mut() {
+            Some(reload) => reload,
+            None => return false,
+        };
+
+        if !reload.watch.poll() {
+            return false;
+        }
+
+        let [vs, fs] = match (reload.load)() {
+            Ok((src, paths)) => {
+                // included files may be changed
+                reload.watch.set_paths(paths);
+                src
+            }
+            Err(err) => {
+                log::error!("failed to load shader: {}", err);
+                return false;
+            }
+        };
+
+        let (shd, pip) = (reload.build)(&vs, &fs);
+
+        if rg::query_shader_state(shd) != rg::ResourceState::Valid
+            || rg::query_pipeline_state(pip) != rg::ResourceState::Valid
+        {
+            log::error!(
+                "failed to compile shader {:?}, keeping the old one",
+                reload.watch.paths
+            );
+            rg::Shader::destroy(shd);
+            rg::Pipeline::destroy(pip);
+            return false;
+        }
+
+        log::info!("reloaded shader {:?}", reload.watch.paths);
+        rg::Shader::destroy(self.shd);
+        rg::Pipeline::destroy(self.pip);
+        self.shd = shd;
+        self.pip = pip;
+
+        true
     }
 
     pub fn set_vs_uniform(&self, ix: usize, bytes: &[u8]) {
@@ -31,7 +187,107 @@ impl Shader {
         rg::apply_uniforms(rg::ShaderStage::Fs, ix as u32, bytes);
     }
 
+    /// Uploads the uniform block to the vertex shader slot 0
+    pub fn apply_vs<T: UniformBlock>(&self, block: &T) {
+        self.set_vs_uniform(0, block.as_bytes());
+    }
+
+    /// Uploads the uniform block to the fragment shader slot 0
+    pub fn apply_fs<T: UniformBlock>(&self, block: &T) {
+        self.set_fs_uniform(0, block.as_bytes());
+    }
+
+    /// Uploads the uniform block to the vertex shader slot `ix`
+    pub fn apply_vs_at<T: UniformBlock>(&self, ix: usize, block: &T) {
+        self.set_vs_uniform(ix, block.as_bytes());
+    }
+
+    /// Uploads the uniform block to the fragment shader slot `ix`
+    pub fn apply_fs_at<T: UniformBlock>(&self, ix: usize, block: &T) {
+        self.set_fs_uniform(ix, block.as_bytes());
+    }
+
     pub fn apply_pip(&self) {
         rg::apply_pipeline(self.pip);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+
+    /// Temporary file removed on drop
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "rokol-learn-opengl-{}-{}",
+                std::process::id(),
+                name
+            ));
+            std::fs::write(&path, "void main() {}").unwrap();
+            Self(path)
+        }
+
+        /// Moves the modification time forward
+        fn touch(&self) {
+            let mtime = self::mtime(&self.0).unwrap() + Duration::from_secs(1);
+            File::options()
+                .write(true)
+                .open(&self.0)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn unchanged_file_is_not_reloaded() {
+        let file = TempFile::new("unchanged.fs");
+        let mut watch = FileWatch::new(vec![file.0.clone()]);
+        assert!(!watch.check());
+        assert!(!watch.check());
+    }
+
+    #[test]
+    fn touched_file_is_reloaded_once() {
+        let file = TempFile::new("touched.fs");
+        let mut watch = FileWatch::new(vec![file.0.clone()]);
+
+        file.touch();
+        assert!(watch.check());
+        assert!(!watch.check());
+    }
+
+    #[test]
+    fn included_files_are_watched() {
+        let main = TempFile::new("main.fs");
+        let inc = TempFile::new("inc.glsl");
+        let mut watch = FileWatch::new(vec![main.0.clone()]);
+
+        watch.set_paths(vec![main.0.clone(), inc.0.clone()]);
+        assert!(!watch.check());
+
+        inc.touch();
+        assert!(watch.check());
+    }
+
+    #[test]
+    fn poll_is_rate_limited() {
+        let file = TempFile::new("polled.fs");
+        let mut watch = FileWatch::new(vec![file.0.clone()]);
+
+        file.touch();
+        assert!(!watch.poll());
+        assert!(watch.check());
+    }
+}
@@ -1,18 +1,119 @@
 use {
-    image::GenericImageView,
+    image::{DynamicImage, GenericImageView},
     rokol::gfx::{self as rg, BakedResource},
-    std::{borrow::Cow, path::Path},
+    std::{borrow::Cow, fmt, fs, io, path::Path},
 };
 
-/// Image loading result
-pub type Result<T> = image::ImageResult<T>;
+use crate::gfx::mipmap::{self, MipFilter};
+
+/// Error on creating textures
+#[derive(Debug)]
+pub enum TextureError {
+    Image(image::ImageError),
+    /// Pixel buffer length doesn't match width x height x bytes per pixel
+    BufferSize {
+        format: rg::PixelFormat,
+        size: [u32; 2],
+        expected: usize,
+        actual: usize,
+    },
+    /// The pixel format can't be uploaded from the CPU
+    UnsupportedFormat(rg::PixelFormat),
+    /// Faces of a cubemap are not squares of the same size
+    CubemapFaceSize,
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Image(err) => write!(f, "{}", err),
+            Self::BufferSize {
+                format,
+                size,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{}x{} {:?} image needs {} bytes, but the buffer has {} bytes",
+                size[0], size[1], format, expected, actual
+            ),
+            Self::UnsupportedFormat(format) => write!(f, "unsupported pixel format: {:?}", format),
+            Self::CubemapFaceSize => write!(f, "cubemap faces must be squares of the same size"),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Image(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for TextureError {
+    fn from(err: image::ImageError) -> Self {
+        Self::Image(err)
+    }
+}
+
+impl From<io::Error> for TextureError {
+    fn from(err: io::Error) -> Self {
+        Self::Image(image::ImageError::IoError(err))
+    }
+}
+
+/// Texture creation result
+pub type Result<T> = std::result::Result<T, TextureError>;
+
+/// Bytes per pixel of formats that can be uploaded with [`TextureBuilder`]
+pub fn bytes_per_pixel(format: rg::PixelFormat) -> Option<usize> {
+    Some(match format {
+        rg::PixelFormat::R8 => 1,
+        rg::PixelFormat::Rg8 => 2,
+        rg::PixelFormat::Rgba8 | rg::PixelFormat::Srgb8a8 => 4,
+        rg::PixelFormat::Rgba16f => 8,
+        rg::PixelFormat::Rgba32f => 16,
+        _ => return None,
+    })
+}
+
+/// Checks that `len` bytes hold a `w` x `h` image of `format`
+pub fn check_buffer_size(format: rg::PixelFormat, w: u32, h: u32, len: usize) -> Result<()> {
+    let bpp = self::bytes_per_pixel(format).ok_or(TextureError::UnsupportedFormat(format))?;
+    let expected = w as usize * h as usize * bpp;
+
+    if len == expected {
+        Ok(())
+    } else {
+        Err(TextureError::BufferSize {
+            format,
+            size: [w, h],
+            expected,
+            actual: len,
+        })
+    }
+}
 
 #[derive(Debug)]
 pub struct TextureBuilder<'a> {
     pixels: Cow<'a, [u8]>,
     size: [u32; 2],
+    /// Format of `pixels` (`RGBA8` for sRGB pixels)
+    format: rg::PixelFormat,
+    /// Uploads `RGBA8` pixels as sRGB
+    srgb: bool,
+    /// Set if `pixels` is a single-channel or gray + alpha image decoded from a file
+    gray: bool,
+    /// Expands gray images to `RGBA8` instead of uploading them as they are
+    expand_gray: bool,
     filter: rg::Filter,
+    /// Overrides the minification filter
+    min_filter: Option<rg::Filter>,
     wrap: rg::Wrap,
+    /// Generates mipmaps if set
+    mipmaps: Option<MipFilter>,
 }
 
 impl TextureBuilder<'static> {
@@ -24,51 +125,180 @@ impl TextureBuilder<'static> {
         Ok(Self::from_dyn_img(image::load_from_memory(mem)?))
     }
 
-    fn from_dyn_img(img: image::DynamicImage) -> Self {
-        let size = [img.width(), img.height()];
+    /// Loads a Radiance HDR image (`.hdr`) into `RGBA32F`
+    pub fn from_hdr_path(path: &Path) -> Result<Self> {
+        let file = io::BufReader::new(fs::File::open(path)?);
+        let decoder = image::hdr::HdrDecoder::new(file)?;
+        let meta = decoder.metadata();
+        let [w, h] = [meta.width, meta.height];
+        let rgb = decoder.read_image_hdr()?;
+
+        // [OpenGL] invert vertically
+        let mut pixels = Vec::with_capacity(rgb.len() * 16);
+        for row in rgb.chunks(w as usize).rev() {
+            for px in row {
+                for x in &[px[0], px[1], px[2], 1.0] {
+                    pixels.extend_from_slice(&x.to_ne_bytes());
+                }
+            }
+        }
+
+        Ok(Self::new(Cow::from(pixels), w, h, rg::PixelFormat::Rgba32f))
+    }
+
+    /// Single-channel images are loaded as `R8` and gray + alpha images as `RG8` (expanded to
+    /// `RGBA8` with [`TextureBuilder::expand_gray`]). Others are converted to `RGBA8`
+    fn from_dyn_img(img: DynamicImage) -> Self {
+        let [w, h] = [img.width(), img.height()];
 
         // [OpenGL] invert vertically
         let img = img.flipv();
 
-        // NOTE: It can be, for example, RGB8 image
-        let img: Vec<u8> = img.into_rgba8().into_raw();
+        let (pixels, format) = match img {
+            DynamicImage::ImageLuma8(img) => (img.into_raw(), rg::PixelFormat::R8),
+            DynamicImage::ImageLumaA8(img) => (img.into_raw(), rg::PixelFormat::Rg8),
+            img => (img.into_rgba8().into_raw(), rg::PixelFormat::Rgba8),
+        };
 
-        Self {
-            pixels: Cow::from(img),
-            size,
-            filter: rg::Filter::Linear,
-            wrap: rg::Wrap::ClampToEdge,
-        }
+        let mut builder = Self::new(Cow::from(pixels), w, h, format);
+        builder.gray = format != rg::PixelFormat::Rgba8;
+        builder
     }
 }
 
 impl<'a> TextureBuilder<'a> {
-    pub fn from_pixels(pixels: &'a [u8], w: u32, h: u32) -> Self {
+    /// `pixels` must be validated
+    fn new(pixels: Cow<'a, [u8]>, w: u32, h: u32, format: rg::PixelFormat) -> Self {
+        let srgb = format == rg::PixelFormat::Srgb8a8;
         Self {
-            pixels: Cow::from(pixels),
+            pixels,
             size: [w, h],
+            format: if srgb { rg::PixelFormat::Rgba8 } else { format },
+            srgb,
+            gray: false,
+            expand_gray: false,
             filter: rg::Filter::Linear,
+            min_filter: None,
             wrap: rg::Wrap::ClampToEdge,
+            mipmaps: None,
         }
     }
 
+    /// `RGBA8` pixels
+    pub fn from_pixels(pixels: &'a [u8], w: u32, h: u32) -> Result<Self> {
+        Self::from_pixels_with_format(pixels, w, h, rg::PixelFormat::Rgba8)
+    }
+
+    /// Fails if the buffer length doesn't match the size and the format
+    pub fn from_pixels_with_format(
+        pixels: &'a [u8],
+        w: u32,
+        h: u32,
+        format: rg::PixelFormat,
+    ) -> Result<Self> {
+        self::check_buffer_size(format, w, h, pixels.len())?;
+        Ok(Self::new(Cow::from(pixels), w, h, format))
+    }
+
+    /// Pixel format of the texture
+    pub fn format(&self) -> rg::PixelFormat {
+        let format = if self.expands_gray() {
+            rg::PixelFormat::Rgba8
+        } else {
+            self.format
+        };
+
+        match format {
+            rg::PixelFormat::Rgba8 if self.srgb => rg::PixelFormat::Srgb8a8,
+            format => format,
+        }
+    }
+
+    fn expands_gray(&self) -> bool {
+        self.gray && self.expand_gray
+    }
+
+    /// Treats `RGBA8` pixels as sRGB (decoded to linear on sampling). No-op for other formats
+    pub fn srgb(&mut self, srgb: bool) -> &mut Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Expands single-channel (`R8`) and gray + alpha (`RG8`) images decoded from files to `RGBA8`
+    /// so that they can be sampled like color images (and get sRGB and mipmap support)
+    pub fn expand_gray(&mut self, expand: bool) -> &mut Self {
+        self.expand_gray = expand;
+        self
+    }
+
     pub fn filter(&mut self, filter: rg::Filter) -> &mut Self {
         self.filter = filter;
         self
     }
 
+    /// Minification filter such as [`rg::Filter::LinearMipmapLinear`]. By default, it's
+    /// [`TextureBuilder::filter`] (or its mipmap variant if mipmaps are generated)
+    pub fn min_filter(&mut self, filter: rg::Filter) -> &mut Self {
+        self.min_filter = Some(filter);
+        self
+    }
+
     pub fn wrap(&mut self, wrap: rg::Wrap) -> &mut Self {
         self.wrap = wrap;
         self
     }
 
+    /// Generates the full mip chain on the CPU
+    pub fn mipmaps(&mut self, filter: MipFilter) -> &mut Self {
+        self.mipmaps = Some(filter);
+        self
+    }
+
     pub fn build_texture(&self) -> Texture2dDrop {
+        let [w, h] = self.size;
+        let format = self.format();
+
+        let pixels = if self.expands_gray() {
+            Cow::from(self::gray_to_rgba(&self.pixels, self.format))
+        } else {
+            Cow::from(self.pixels.as_ref())
+        };
+
+        let levels = match self.mipmaps {
+            Some(filter) if format == rg::PixelFormat::Rgba8 => {
+                mipmap::gen_mip_chain(&pixels, w, h, filter)
+            }
+            Some(filter) if format == rg::PixelFormat::Srgb8a8 => {
+                mipmap::gen_srgb_mip_chain(&pixels, w, h, filter)
+            }
+            Some(_) => {
+                log::warn!("mipmaps are not supported for {:?}", format);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+
+        let min_filter = match self.min_filter {
+            Some(filter) => filter,
+            None if levels.is_empty() => self.filter,
+            None => self::mipmap_filter(self.filter),
+        };
+
         Texture2dDrop {
             img: rg::Image::create(&{
-                let mut desc = self::img_desc(self.size[0], self.size[1], self.filter, self.wrap);
+                let mut desc = self::img_desc(w, h, self.filter, self.wrap);
+                desc.pixel_format = format as u32;
+                desc.min_filter = min_filter as u32;
                 desc.render_target = false;
                 desc.usage = rg::ResourceUsage::Immutable as u32;
-                desc.data.subimage[0][0] = self.pixels.as_ref().into();
+                if levels.is_empty() {
+                    desc.data.subimage[0][0] = pixels.as_ref().into();
+                } else {
+                    desc.num_mipmaps = levels.len() as i32;
+                    for (i, level) in levels.iter().enumerate() {
+                        desc.data.subimage[0][i] = level.as_slice().into();
+                    }
+                }
                 desc
             }),
             w: self.size[0],
@@ -77,6 +307,96 @@ impl<'a> TextureBuilder<'a> {
     }
 }
 
+/// Builder of [`TextureCube`]
+///
+/// Faces are in the order of +X, -X, +Y, -Y, +Z, -Z (right, left, top, bottom, front, back).
+/// Unlike 2D textures, face images are not flipped: cubemaps put the origin at the top-left.
+#[derive(Debug)]
+pub struct CubemapBuilder {
+    /// RGBA8 pixels of each face
+    faces: Vec<Vec<u8>>,
+    /// Width and height of each face
+    size: u32,
+    filter: rg::Filter,
+}
+
+impl CubemapBuilder {
+    pub fn from_paths(paths: [&Path; 6]) -> Result<Self> {
+        let mut imgs = Vec::with_capacity(6);
+        for path in paths.iter() {
+            imgs.push(image::open(path)?);
+        }
+        Self::from_dyn_imgs(imgs)
+    }
+
+    pub fn from_encoded_bytes(faces: [&[u8]; 6]) -> Result<Self> {
+        let mut imgs = Vec::with_capacity(6);
+        for mem in faces.iter() {
+            imgs.push(image::load_from_memory(mem)?);
+        }
+        Self::from_dyn_imgs(imgs)
+    }
+
+    /// Fails unless the faces are squares of the same size
+    fn from_dyn_imgs(imgs: Vec<DynamicImage>) -> Result<Self> {
+        let size = imgs[0].width();
+        if imgs.iter().any(|img| img.dimensions() != (size, size)) {
+            return Err(TextureError::CubemapFaceSize);
+        }
+
+        Ok(Self {
+            faces: imgs
+                .into_iter()
+                .map(|img| img.into_rgba8().into_raw())
+                .collect(),
+            size,
+            filter: rg::Filter::Linear,
+        })
+    }
+
+    pub fn filter(&mut self, filter: rg::Filter) -> &mut Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn build_texture(&self) -> TextureCube {
+        TextureCube {
+            img: rg::Image::create(&{
+                let mut desc =
+                    self::img_desc(self.size, self.size, self.filter, rg::Wrap::ClampToEdge);
+                desc.type_ = rg::ImageType::Cube as u32;
+                desc.usage = rg::ResourceUsage::Immutable as u32;
+                for (i, face) in self.faces.iter().enumerate() {
+                    desc.data.subimage[i][0] = face.as_slice().into();
+                }
+                desc
+            }),
+            size: self.size,
+        }
+    }
+}
+
+/// Expands `R8` or `RG8` (gray + alpha) pixels to `RGBA8`
+fn gray_to_rgba(pixels: &[u8], format: rg::PixelFormat) -> Vec<u8> {
+    let bpp = if format == rg::PixelFormat::Rg8 { 2 } else { 1 };
+    let mut rgba = Vec::with_capacity(pixels.len() / bpp * 4);
+    for px in pixels.chunks(bpp) {
+        let alpha = if bpp == 2 { px[1] } else { 255 };
+        rgba.extend_from_slice(&[px[0], px[0], px[0], alpha]);
+    }
+    rgba
+}
+
+/// Mipmap-aware variant of a filter
+fn mipmap_filter(filter: rg::Filter) -> rg::Filter {
+    match filter {
+        rg::Filter::Nearest => rg::Filter::NearestMipmapNearest,
+        rg::Filter::Linear => rg::Filter::LinearMipmapLinear,
+        // already mipmap-aware
+        _ => filter,
+    }
+}
+
 fn img_desc(w: u32, h: u32, filter: rg::Filter, wrap: rg::Wrap) -> rg::ImageDesc {
     rg::ImageDesc {
         type_: rg::ImageType::Dim2 as u32,
@@ -91,23 +411,23 @@ fn img_desc(w: u32, h: u32, filter: rg::Filter, wrap: rg::Wrap) -> rg::ImageDesc
     }
 }
 
-// fn target_desc(w: u32, h: u32, filter: rg::Filter, wrap: rg::Wrap) -> rg::ImageDesc {
-//     let mut desc = image_desc_2d(w, h, filter, wrap);
-//     desc.render_target = true;
-//     // TODO:
-//     // desc.usage = rg::ResourceUsage::Dynamic as u32;
-//     desc
-// }
+fn target_desc(b: &RenderTextureBuilder, format: Option<rg::PixelFormat>) -> rg::ImageDesc {
+    let mut desc = self::img_desc(b.size[0], b.size[1], b.filter, b.wrap);
+    desc.render_target = true;
+    if let Some(format) = format {
+        desc.pixel_format = format as u32;
+    }
+    desc.sample_count = b.sample_count as i32;
+    desc
+}
 
-// fn stencil_desc(w: u32, h: u32, filter: rg::Filter, wrap: rg::Wrap) -> rg::ImageDesc {
-//     let mut desc = image_desc_2d(w, h, filter, wrap);
-//     desc.render_target = true;
-//     desc.pixel_format = rg::PixelFormat::DepthStencil as u32;
-//     // TODO:
-//     // desc.usage = rg::ResourceUsage::Dynamic as u32;
-//     // desc.sample_count = 1;
-//     desc
-// }
+fn depth_stencil_desc(b: &RenderTextureBuilder) -> rg::ImageDesc {
+    let mut desc = self::img_desc(b.size[0], b.size[1], b.filter, b.wrap);
+    desc.render_target = true;
+    desc.pixel_format = rg::PixelFormat::DepthStencil as u32;
+    desc.sample_count = b.sample_count as i32;
+    desc
+}
 
 /// Owned 2D texture
 #[derive(Debug, Default)]
@@ -146,13 +466,165 @@ impl Texture2dDrop {
     }
 }
 
-/// Off-screen 2D rendering target
+/// Owned cubemap texture
 #[derive(Debug, Default)]
+pub struct TextureCube {
+    img: rg::Image,
+    /// Width and height of each face
+    size: u32,
+}
+
+impl Drop for TextureCube {
+    fn drop(&mut self) {
+        rg::Image::destroy(self.img);
+    }
+}
+
+impl TextureCube {
+    /// Width and height of each face
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn img(&self) -> rg::Image {
+        self.img
+    }
+}
+
+/// Builder of [`RenderTexture2d`]
+#[derive(Debug, Clone)]
+pub struct RenderTextureBuilder {
+    size: [u32; 2],
+    /// `None`: default color format of the backend
+    format: Option<rg::PixelFormat>,
+    filter: rg::Filter,
+    wrap: rg::Wrap,
+    sample_count: u32,
+    depth_stencil: bool,
+}
+
+impl RenderTextureBuilder {
+    /// Default color format, linear filter, no MSAA and with a depth-stencil attachment
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            size: [w, h],
+            format: None,
+            filter: rg::Filter::Linear,
+            wrap: rg::Wrap::ClampToEdge,
+            sample_count: 1,
+            depth_stencil: true,
+        }
+    }
+
+    pub fn size(&mut self, w: u32, h: u32) -> &mut Self {
+        self.size = [w, h];
+        self
+    }
+
+    /// Pixel format of the color attachment
+    pub fn format(&mut self, format: rg::PixelFormat) -> &mut Self {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn filter(&mut self, filter: rg::Filter) -> &mut Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn wrap(&mut self, wrap: rg::Wrap) -> &mut Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// MSAA sample count (1 to disable)
+    pub fn sample_count(&mut self, n: u32) -> &mut Self {
+        self.sample_count = n.max(1);
+        self
+    }
+
+    /// Whether to create a depth-stencil attachment
+    pub fn depth_stencil(&mut self, b: bool) -> &mut Self {
+        self.depth_stencil = b;
+        self
+    }
+
+    pub fn build(&self) -> RenderTexture2d {
+        let (mut colors, depth, pass) = self.create(&[self.format]);
+
+        RenderTexture2d {
+            tex: colors.remove(0),
+            depth,
+            pass,
+            builder: self.clone(),
+        }
+    }
+
+    /// Builds a target with a color attachment per format, rendered in one pass
+    ///
+    /// Panics if the number of formats is zero or more than the backend supports.
+    pub fn build_multi(&self, formats: &[rg::PixelFormat]) -> MultiRenderTarget {
+        let formats: Vec<_> = formats.iter().map(|f| Some(*f)).collect();
+        let (colors, depth, pass) = self.create(&formats);
+
+        MultiRenderTarget {
+            colors,
+            depth,
+            pass,
+            builder: self.clone(),
+            formats,
+        }
+    }
+
+    /// Creates color attachments, optional depth-stencil attachment and the pass
+    fn create(
+        &self,
+        formats: &[Option<rg::PixelFormat>],
+    ) -> (Vec<Texture2dDrop>, Option<Texture2dDrop>, rg::Pass) {
+        let [w, h] = self.size;
+        let mut desc = rg::PassDesc::default();
+        assert!(
+            !formats.is_empty() && formats.len() <= desc.color_attachments.len(),
+            "invalid number of color attachments: {}",
+            formats.len()
+        );
+
+        let colors: Vec<_> = formats
+            .iter()
+            .map(|f| Texture2dDrop::new(rg::Image::create(&self::target_desc(self, *f)), w, h))
+            .collect();
+
+        let depth = if self.depth_stencil {
+            let img = rg::Image::create(&self::depth_stencil_desc(self));
+            Some(Texture2dDrop::new(img, w, h))
+        } else {
+            None
+        };
+
+        for (i, tex) in colors.iter().enumerate() {
+            desc.color_attachments[i].image = tex.img();
+        }
+        if let Some(depth) = depth.as_ref() {
+            desc.depth_stencil_attachment.image = depth.img();
+        }
+
+        (colors, depth, rg::Pass::create(&desc))
+    }
+}
+
+/// Off-screen 2D rendering target
+///
+/// The attachments and the pass are destroyed on drop.
+#[derive(Debug)]
 pub struct RenderTexture2d {
     /// Render target texture binded to the rendering pass
     tex: Texture2dDrop,
+    /// Depth-stencil attachment
+    depth: Option<Texture2dDrop>,
     /// Off-screen rendering pass
     pass: rg::Pass,
+    /// Settings used to create the target
+    builder: RenderTextureBuilder,
 }
 
 impl Drop for RenderTexture2d {
@@ -162,6 +634,15 @@ impl Drop for RenderTexture2d {
 }
 
 impl RenderTexture2d {
+    /// Render target with the default settings of [`RenderTextureBuilder`]
+    pub fn new(w: u32, h: u32) -> Self {
+        RenderTextureBuilder::new(w, h).build()
+    }
+
+    pub fn builder(w: u32, h: u32) -> RenderTextureBuilder {
+        RenderTextureBuilder::new(w, h)
+    }
+
     /// [`rokol::gfx::Pass`] for off-screen rendering
     pub fn pass(&self) -> rg::Pass {
         self.pass
@@ -171,6 +652,27 @@ impl RenderTexture2d {
         &self.tex
     }
 
+    /// Depth-stencil attachment
+    pub fn depth(&self) -> Option<&Texture2dDrop> {
+        self.depth.as_ref()
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.builder.sample_count
+    }
+
+    /// Recreates the attachments and the pass with the new size
+    pub fn resize(&mut self, w: u32, h: u32) {
+        if self.size() == [w, h] {
+            return;
+        }
+
+        let mut builder = self.builder.clone();
+        builder.size(w, h);
+        // drops the old target
+        *self = builder.build();
+    }
+
     pub fn w(&self) -> u32 {
         self.tex.w
     }
@@ -187,3 +689,192 @@ impl RenderTexture2d {
         self.tex.img
     }
 }
+
+/// Off-screen rendering target with multiple color attachments (e.g. G-buffer)
+///
+/// Create it with [`RenderTextureBuilder::build_multi`]. The attachments and the pass are
+/// destroyed on drop.
+#[derive(Debug)]
+pub struct MultiRenderTarget {
+    /// Color attachments in the order of fragment shader outputs
+    colors: Vec<Texture2dDrop>,
+    /// Depth-stencil attachment
+    depth: Option<Texture2dDrop>,
+    /// Off-screen rendering pass writing to all the attachments
+    pass: rg::Pass,
+    /// Settings used to create the target
+    builder: RenderTextureBuilder,
+    /// Pixel formats of the color attachments
+    formats: Vec<Option<rg::PixelFormat>>,
+}
+
+impl Drop for MultiRenderTarget {
+    fn drop(&mut self) {
+        rg::Pass::destroy(self.pass);
+    }
+}
+
+impl MultiRenderTarget {
+    /// [`rokol::gfx::Pass`] for off-screen rendering
+    pub fn pass(&self) -> rg::Pass {
+        self.pass
+    }
+
+    /// Color attachments
+    pub fn colors(&self) -> &[Texture2dDrop] {
+        &self.colors
+    }
+
+    /// Image of the `i`-th color attachment
+    pub fn img(&self, i: usize) -> rg::Image {
+        self.colors[i].img
+    }
+
+    /// Depth-stencil attachment
+    pub fn depth(&self) -> Option<&Texture2dDrop> {
+        self.depth.as_ref()
+    }
+
+    pub fn w(&self) -> u32 {
+        self.builder.size[0]
+    }
+
+    pub fn h(&self) -> u32 {
+        self.builder.size[1]
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.builder.size
+    }
+
+    /// Recreates all the attachments and the pass with the new size
+    pub fn resize(&mut self, w: u32, h: u32) {
+        if self.size() == [w, h] {
+            return;
+        }
+
+        let mut builder = self.builder.clone();
+        builder.size(w, h);
+        let (colors, depth, pass) = builder.create(&self.formats);
+
+        rg::Pass::destroy(self.pass);
+        self.colors = colors;
+        self.depth = depth;
+        self.pass = pass;
+        self.builder = builder;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(img: DynamicImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        img.write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn buffer_size() {
+        assert!(self::check_buffer_size(rg::PixelFormat::Rgba8, 2, 3, 24).is_ok());
+        assert!(self::check_buffer_size(rg::PixelFormat::R8, 3, 1, 3).is_ok());
+        assert!(self::check_buffer_size(rg::PixelFormat::Rgba32f, 1, 1, 16).is_ok());
+
+        match self::check_buffer_size(rg::PixelFormat::Rgba8, 2, 2, 15) {
+            Err(TextureError::BufferSize {
+                size,
+                expected,
+                actual,
+                ..
+            }) => assert_eq!((size, expected, actual), ([2, 2], 16, 15)),
+            res => panic!("{:?}", res),
+        }
+
+        match self::check_buffer_size(rg::PixelFormat::Depth, 1, 1, 4) {
+            Err(TextureError::UnsupportedFormat(rg::PixelFormat::Depth)) => {}
+            res => panic!("{:?}", res),
+        }
+    }
+
+    #[test]
+    fn error_message() {
+        let err = TextureBuilder::from_pixels(&[0; 15], 2, 2).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2x2 Rgba8 image needs 16 bytes, but the buffer has 15 bytes"
+        );
+    }
+
+    #[test]
+    fn srgb_format() {
+        let pixels = [0; 4];
+        let mut builder = TextureBuilder::from_pixels(&pixels, 1, 1).unwrap();
+        assert_eq!(builder.format(), rg::PixelFormat::Rgba8);
+        builder.srgb(true);
+        assert_eq!(builder.format(), rg::PixelFormat::Srgb8a8);
+        builder.srgb(false);
+        assert_eq!(builder.format(), rg::PixelFormat::Rgba8);
+
+        let mut builder =
+            TextureBuilder::from_pixels_with_format(&pixels, 1, 1, rg::PixelFormat::Srgb8a8)
+                .unwrap();
+        assert_eq!(builder.format(), rg::PixelFormat::Srgb8a8);
+        builder.srgb(false);
+        assert_eq!(builder.format(), rg::PixelFormat::Rgba8);
+
+        // no-op
+        let mut builder =
+            TextureBuilder::from_pixels_with_format(&pixels, 4, 1, rg::PixelFormat::R8).unwrap();
+        builder.srgb(true);
+        assert_eq!(builder.format(), rg::PixelFormat::R8);
+    }
+
+    #[test]
+    fn gray_images_keep_channels() {
+        let gray = image::GrayImage::from_raw(2, 1, vec![10, 20]).unwrap();
+        let bytes = self::png(DynamicImage::ImageLuma8(gray));
+
+        let mut builder = TextureBuilder::from_encoded_bytes(&bytes).unwrap();
+        assert_eq!(builder.format(), rg::PixelFormat::R8);
+        // no-op
+        builder.srgb(true);
+        assert_eq!(builder.format(), rg::PixelFormat::R8);
+
+        let gray_alpha = image::GrayAlphaImage::from_raw(1, 1, vec![10, 20]).unwrap();
+        let bytes = self::png(DynamicImage::ImageLumaA8(gray_alpha));
+        let builder = TextureBuilder::from_encoded_bytes(&bytes).unwrap();
+        assert_eq!(builder.format(), rg::PixelFormat::Rg8);
+    }
+
+    #[test]
+    fn gray_images_are_expanded_on_request() {
+        let gray = image::GrayImage::from_raw(2, 1, vec![10, 20]).unwrap();
+        let bytes = self::png(DynamicImage::ImageLuma8(gray));
+
+        let mut builder = TextureBuilder::from_encoded_bytes(&bytes).unwrap();
+        builder.expand_gray(true);
+        assert_eq!(builder.format(), rg::PixelFormat::Rgba8);
+        builder.srgb(true);
+        assert_eq!(builder.format(), rg::PixelFormat::Srgb8a8);
+
+        let gray_alpha = image::GrayAlphaImage::from_raw(1, 1, vec![10, 20]).unwrap();
+        let bytes = self::png(DynamicImage::ImageLumaA8(gray_alpha));
+        let mut builder = TextureBuilder::from_encoded_bytes(&bytes).unwrap();
+        builder.expand_gray(true);
+        assert_eq!(builder.format(), rg::PixelFormat::Rgba8);
+    }
+
+    #[test]
+    fn gray_expansion() {
+        assert_eq!(
+            super::gray_to_rgba(&[10, 20], rg::PixelFormat::R8),
+            vec![10, 10, 10, 255, 20, 20, 20, 255]
+        );
+        assert_eq!(
+            super::gray_to_rgba(&[10, 20], rg::PixelFormat::Rg8),
+            vec![10, 10, 10, 20]
+        );
+    }
+}
@@ -0,0 +1,383 @@
+/*!
+Type-safe uniform blocks
+
+Define uniform blocks with [`uniform_block!`](crate::uniform_block) and upload them with
+[`Shader::apply_vs`](crate::gfx::Shader::apply_vs) or [`Shader::apply_fs`](crate::gfx::Shader::apply_fs):
+
+```ignore
+uniform_block! {
+    /// Uniforms of the vertex shader
+    pub struct CubeUniforms {
+        mvp: glam::Mat4,
+    }
+}
+
+shd.vs.uniform_blocks[0] = uniform_block_desc::<CubeUniforms>();
+```
+
+Fields are laid out in std140 rules, while sokol's GL backend reads uniforms tightly packed. Order
+fields so that both layouts match without gaps (e.g. put a `f32` right after a `[f32; 3]`);
+[`check_layout`] reports mismatches. The block is uploaded without the tail padding.
+*/
+
+use rokol::gfx as rg;
+
+/// Rust type that can be a field of [`UniformBlock`]
+pub trait UniformField {
+    /// GLSL type (of an element if it's an array)
+    const TYPE: rg::UniformType;
+    /// Array length, or 1 if it's not an array
+    const COUNT: usize;
+}
+
+macro_rules! impl_field {
+    ($($ty:ty => $uniform_ty:ident,)*) => {
+        $(
+            impl UniformField for $ty {
+                const TYPE: rg::UniformType = rg::UniformType::$uniform_ty;
+                const COUNT: usize = 1;
+            }
+        )*
+    };
+}
+
+impl_field!(
+    f32 => Float,
+    [f32; 2] => Float2,
+    [f32; 3] => Float3,
+    [f32; 4] => Float4,
+    glam::Vec2 => Float2,
+    glam::Vec3 => Float3,
+    glam::Vec4 => Float4,
+    glam::Mat4 => Mat4,
+);
+
+/// Arrays of `vec4` and `mat4` (other arrays have different strides in std140)
+macro_rules! impl_array_field {
+    ($($n:expr),*) => {
+        $(
+            impl UniformField for [[f32; 4]; $n] {
+                const TYPE: rg::UniformType = rg::UniformType::Float4;
+                const COUNT: usize = $n;
+            }
+
+            impl UniformField for [glam::Vec4; $n] {
+                const TYPE: rg::UniformType = rg::UniformType::Float4;
+                const COUNT: usize = $n;
+            }
+
+            impl UniformField for [glam::Mat4; $n] {
+                const TYPE: rg::UniformType = rg::UniformType::Mat4;
+                const COUNT: usize = $n;
+            }
+        )*
+    };
+}
+
+impl_array_field!(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 32);
+
+/// Field of [`UniformBlock`]
+#[derive(Debug, Clone, Copy)]
+pub struct UniformMember {
+    /// Null-terminated name
+    pub name: &'static str,
+    pub ty: rg::UniformType,
+    /// Array length, or 1 if it's not an array
+    pub count: usize,
+}
+
+impl UniformMember {
+    /// Name without the null terminator
+    pub fn name(&self) -> &'static str {
+        self.name.trim_end_matches('\0')
+    }
+
+    /// Size in bytes when tightly packed
+    pub fn size(&self) -> usize {
+        self::std140_base(self.ty).1 * self.count
+    }
+}
+
+/// Size of uniforms tightly packed, i.e., the uniform block size in sokol's GL backend
+pub fn packed_size(members: &[UniformMember]) -> usize {
+    members.iter().map(|m| m.size()).sum()
+}
+
+/// `#[repr(C)]` struct uploaded as a uniform block. Implement it with
+/// [`uniform_block!`](crate::uniform_block)
+///
+/// # Safety
+///
+/// The implementor must be `#[repr(C)]` and contain only `f32` data, so that it can be viewed as
+/// bytes. The first [`UniformBlock::size`] bytes must not contain padding ([`check_layout`]
+/// passes).
+pub unsafe trait UniformBlock: Sized {
+    /// Fields in declaration order
+    const MEMBERS: &'static [UniformMember];
+
+    /// Byte offsets of the fields in the Rust struct
+    fn offsets() -> Vec<usize>;
+
+    /// Size of the fields without the tail padding
+    fn size() -> usize {
+        self::packed_size(Self::MEMBERS)
+    }
+
+    /// The fields as bytes (without the tail padding)
+    fn as_bytes(&self) -> &[u8] {
+        let size = Self::size();
+        assert!(size <= std::mem::size_of::<Self>());
+        unsafe { std::slice::from_raw_parts(self as *const Self as *const u8, size) }
+    }
+}
+
+/// Byte offsets and size of a uniform block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Std140Layout {
+    pub offsets: Vec<usize>,
+    /// Size rounded up to 16 bytes
+    pub size: usize,
+}
+
+/// (alignment, size) of a non-array uniform in std140 rules
+fn std140_base(ty: rg::UniformType) -> (usize, usize) {
+    match ty {
+        rg::UniformType::Float => (4, 4),
+        rg::UniformType::Float2 => (8, 8),
+        rg::UniformType::Float3 => (16, 12),
+        rg::UniformType::Float4 => (16, 16),
+        rg::UniformType::Mat4 => (16, 64),
+        _ => panic!("invalid uniform type: {:?}", ty),
+    }
+}
+
+/// `align` must be a power of two
+fn round_up(x: usize, align: usize) -> usize {
+    (x + align - 1) & !(align - 1)
+}
+
+/// Computes the std140 layout of uniforms
+pub fn std140_layout(members: &[UniformMember]) -> Std140Layout {
+    let mut offsets = Vec::with_capacity(members.len());
+    let mut offset = 0;
+
+    for m in members {
+        let (align, size) = self::std140_base(m.ty);
+
+        let (align, size) = if m.count > 1 {
+            // array elements are aligned to vec4
+            let stride = self::round_up(size, 16);
+            (16, stride * m.count)
+        } else {
+            (align, size)
+        };
+
+        offset = self::round_up(offset, align);
+        offsets.push(offset);
+        offset += size;
+    }
+
+    Std140Layout {
+        offsets,
+        size: self::round_up(offset, 16),
+    }
+}
+
+/// Checks if the Rust layout of `T` matches the std140 layout without gaps between the fields
+pub fn check_layout<T: UniformBlock>() -> Result<(), String> {
+    let layout = self::std140_layout(T::MEMBERS);
+    let offsets = T::offsets();
+
+    let mut packed = 0;
+    for (i, m) in T::MEMBERS.iter().enumerate() {
+        if offsets[i] != layout.offsets[i] {
+            return Err(format!(
+                "uniform `{}` of `{}` is at offset {}, but std140 requires {}",
+                m.name(),
+                std::any::type_name::<T>(),
+                offsets[i],
+                layout.offsets[i],
+            ));
+        }
+
+        if layout.offsets[i] != packed {
+            return Err(format!(
+                "uniform `{}` of `{}` is at offset {} in std140, but {} when tightly packed \
+                 (reorder the fields to remove the gap)",
+                m.name(),
+                std::any::type_name::<T>(),
+                layout.offsets[i],
+                packed,
+            ));
+        }
+        packed += m.size();
+    }
+
+    let size = std::mem::size_of::<T>();
+    if size != layout.size {
+        return Err(format!(
+            "size of `{}` is {}, but std140 requires {}",
+            std::any::type_name::<T>(),
+            size,
+            layout.size
+        ));
+    }
+
+    Ok(())
+}
+
+/// Creates [`rg::ShaderUniformBlockDesc`] with uniform names. The block size is
+/// [`UniformBlock::size`]
+///
+/// Panics if the layout of `T` doesn't match std140 without gaps.
+pub fn uniform_block_desc<T: UniformBlock>() -> rg::ShaderUniformBlockDesc {
+    if let Err(err) = self::check_layout::<T>() {
+        panic!("{}", err);
+    }
+
+    let mut block = rg::ShaderUniformBlockDesc::default();
+    assert!(
+        T::MEMBERS.len() <= block.uniforms.len(),
+        "too many uniforms in `{}`",
+        std::any::type_name::<T>()
+    );
+
+    for (i, m) in T::MEMBERS.iter().enumerate() {
+        block.uniforms[i] = rg::ShaderUniformDesc {
+            name: m.name.as_ptr() as *const _,
+            type_: m.ty as u32,
+            array_count: m.count as _,
+            ..Default::default()
+        };
+    }
+    block.size = T::size() as _;
+
+    block
+}
+
+/// Defines a `#[repr(C)]` struct implementing [`UniformBlock`](crate::gfx::UniformBlock)
+///
+/// Field names must match the uniform names in the GLSL source.
+#[macro_export]
+macro_rules! uniform_block {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Default)]
+        #[repr(C, align(16))]
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        unsafe impl $crate::gfx::UniformBlock for $name {
+            const MEMBERS: &'static [$crate::gfx::UniformMember] = &[
+                $(
+                    $crate::gfx::UniformMember {
+                        name: concat!(stringify!($field), "\0"),
+                        ty: <$ty as $crate::gfx::UniformField>::TYPE,
+                        count: <$ty as $crate::gfx::UniformField>::COUNT,
+                    },
+                )*
+            ];
+
+            fn offsets() -> Vec<usize> {
+                let x = Self::default();
+                let base = &x as *const Self as usize;
+                vec![$(&x.$field as *const $ty as usize - base,)*]
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::shaders::{
+        BlurUniforms, CubeUniforms, LightUniforms, LitVsUniforms, MaterialUniforms, PostFxUniforms,
+        SkyboxUniforms, ToneMapUniforms,
+    };
+
+    crate::uniform_block! {
+        struct Gap {
+            a: f32,
+            b: [f32; 4],
+        }
+    }
+
+    fn member(ty: rg::UniformType, count: usize) -> UniformMember {
+        UniformMember {
+            name: "\0",
+            ty,
+            count,
+        }
+    }
+
+    #[test]
+    fn std140_rules() {
+        use rg::UniformType::*;
+
+        let layout = self::std140_layout(&[
+            member(Float, 1),
+            member(Float2, 1),
+            member(Float3, 1),
+            member(Float, 1),
+            member(Float4, 2),
+            member(Mat4, 1),
+            member(Float, 1),
+        ]);
+        assert_eq!(layout.offsets, vec![0, 8, 16, 28, 32, 64, 128]);
+        assert_eq!(layout.size, 144);
+    }
+
+    #[test]
+    fn packed_size_has_no_tail_padding() {
+        use rg::UniformType::*;
+
+        let members = [member(Mat4, 1), member(Float2, 1)];
+        assert_eq!(self::packed_size(&members), 72);
+        assert_eq!(self::std140_layout(&members).size, 80);
+    }
+
+    #[test]
+    fn builtin_blocks() {
+        fn check<T: UniformBlock + Default>(size: usize) {
+            self::check_layout::<T>().unwrap();
+            assert_eq!(T::size(), size, "{}", std::any::type_name::<T>());
+            assert_eq!(T::default().as_bytes().len(), size);
+        }
+
+        check::<CubeUniforms>(64);
+        check::<PostFxUniforms>(72);
+        check::<SkyboxUniforms>(64);
+        check::<ToneMapUniforms>(12);
+        check::<BlurUniforms>(76);
+        check::<LitVsUniforms>(3 * 64);
+        check::<LightUniforms>(6 * 8 * 16 + 12 + 4 + 4);
+        check::<MaterialUniforms>(32);
+    }
+
+    #[test]
+    fn gap_is_rejected() {
+        let err = self::check_layout::<Gap>().unwrap_err();
+        assert!(err.contains("`b`"), "{}", err);
+    }
+
+    #[test]
+    fn block_desc_uses_packed_size() {
+        let block = self::uniform_block_desc::<BlurUniforms>();
+        assert_eq!(block.size as usize, 76);
+        assert_eq!(block.uniforms[2].type_, rg::UniformType::Float as u32);
+        assert_eq!(block.uniforms[0].array_count, 4);
+    }
+}
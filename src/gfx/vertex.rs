@@ -0,0 +1,183 @@
+/*!
+Vertex layouts derived from vertex structs
+
+Define vertex types with [`vertex!`](crate::vertex) and use [`Vertex::layout_desc`] for
+pipelines:
+
+```ignore
+vertex! {
+    /// (position, uv) vertex
+    pub struct QuadVertex {
+        pub pos: [f32; 3],
+        pub uv: [f32; 2],
+    }
+}
+
+let pip = rg::PipelineDesc {
+    layout: QuadVertex::layout_desc(),
+    ..Default::default()
+};
+```
+*/
+
+use rokol::gfx as rg;
+
+/// Rust type that can be a field of [`Vertex`]
+pub trait VertexField {
+    const FORMAT: rg::VertexFormat;
+}
+
+macro_rules! impl_field {
+    ($($ty:ty => $format:ident,)*) => {
+        $(
+            impl VertexField for $ty {
+                const FORMAT: rg::VertexFormat = rg::VertexFormat::$format;
+            }
+        )*
+    };
+}
+
+impl_field!(
+    f32 => Float,
+    [f32; 2] => Float2,
+    [f32; 3] => Float3,
+    [f32; 4] => Float4,
+    // colors: [0, 255] -> [0.0, 1.0]
+    [u8; 4] => UByte4N,
+);
+
+/// Vertex attribute
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexAttr {
+    /// Field name
+    pub name: &'static str,
+    pub format: rg::VertexFormat,
+    /// Byte offset in the vertex struct
+    pub offset: usize,
+}
+
+/// `#[repr(C)]` vertex struct. Implement it with [`vertex!`](crate::vertex)
+pub trait Vertex: Sized {
+    /// Attributes in the order of `layout(location = N)`
+    fn attrs() -> Vec<VertexAttr>;
+
+    /// Layout for a pipeline using this vertex type as the first vertex buffer
+    fn layout_desc() -> rg::LayoutDesc {
+        let mut desc = rg::LayoutDesc::default();
+        let attrs = Self::attrs();
+        assert!(
+            attrs.len() <= desc.attrs.len(),
+            "too many vertex attributes in `{}`",
+            std::any::type_name::<Self>()
+        );
+
+        desc.buffers[0].stride = std::mem::size_of::<Self>() as _;
+        for (i, attr) in attrs.iter().enumerate() {
+            desc.attrs[i].buffer_index = 0;
+            desc.attrs[i].offset = attr.offset as _;
+            desc.attrs[i].format = attr.format as u32;
+        }
+
+        desc
+    }
+}
+
+/// Defines a `#[repr(C)]` struct implementing [`Vertex`](crate::gfx::Vertex)
+///
+/// Fields become vertex attributes in declaration order.
+#[macro_export]
+macro_rules! vertex {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident: $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Default)]
+        #[repr(C)]
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl $crate::gfx::Vertex for $name {
+            fn attrs() -> Vec<$crate::gfx::VertexAttr> {
+                let x = Self::default();
+                let base = &x as *const Self as usize;
+                vec![
+                    $(
+                        $crate::gfx::VertexAttr {
+                            name: stringify!($field),
+                            format: <$ty as $crate::gfx::VertexField>::FORMAT,
+                            offset: &x.$field as *const $ty as usize - base,
+                        },
+                    )*
+                ]
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::vertex! {
+        struct ColorVertex {
+            pos: [f32; 3],
+            color: [u8; 4],
+            uv: [f32; 2],
+            weight: f32,
+        }
+    }
+
+    #[test]
+    fn attrs_are_in_declaration_order() {
+        let attrs = ColorVertex::attrs();
+        let names: Vec<_> = attrs.iter().map(|a| a.name).collect();
+        assert_eq!(names, vec!["pos", "color", "uv", "weight"]);
+
+        let offsets: Vec<_> = attrs.iter().map(|a| a.offset).collect();
+        assert_eq!(offsets, vec![0, 12, 16, 24]);
+
+        let formats: Vec<_> = attrs.iter().map(|a| a.format).collect();
+        assert_eq!(
+            formats,
+            vec![
+                rg::VertexFormat::Float3,
+                rg::VertexFormat::UByte4N,
+                rg::VertexFormat::Float2,
+                rg::VertexFormat::Float,
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_desc() {
+        let desc = ColorVertex::layout_desc();
+        assert_eq!(desc.buffers[0].stride as usize, 28);
+
+        for (i, attr) in ColorVertex::attrs().iter().enumerate() {
+            assert_eq!(desc.attrs[i].buffer_index, 0);
+            assert_eq!(desc.attrs[i].offset as usize, attr.offset);
+            assert_eq!(desc.attrs[i].format, attr.format as u32);
+        }
+
+        // the rest are unused
+        assert_eq!(desc.attrs[4].format, 0);
+    }
+
+    #[test]
+    fn model_vertex_layout() {
+        let desc = crate::assets::ModelVertex::layout_desc();
+        assert_eq!(desc.buffers[0].stride as usize, 32);
+        assert_eq!(desc.attrs[1].offset, 12);
+        assert_eq!(desc.attrs[2].offset, 24);
+    }
+}
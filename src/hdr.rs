@@ -0,0 +1,329 @@
+/*!
+HDR rendering and tone mapping
+
+Render the scene into [`Hdr::scene_pass`] (floating-point render targets), then [`Hdr::resolve`]
+maps it to the screen with the selected [`ToneMap`] operator, optional [`Bloom`] and gamma
+correction:
+
+```ignore
+rg::begin_pass(self.hdr.scene_pass(), &self.pa);
+// draw the scene with pipelines set up with `hdr::scene_pip`
+rg::end_pass();
+
+self.hdr.resolve(ra::width() as u32, ra::height() as u32);
+rg::commit();
+```
+
+The free functions are CPU references of the operators in `tonemap.fs`.
+*/
+
+use rokol::gfx as rg;
+
+use crate::{
+    bloom::Bloom,
+    gfx::{MultiRenderTarget, RenderTexture2d, Shader, StaticMesh},
+    postfx,
+    shaders::{PostVertex, ShaderCache, ToneMapUniforms},
+};
+
+/// Pixel format of the HDR render targets
+pub const HDR_FORMAT: rg::PixelFormat = rg::PixelFormat::Rgba16f;
+
+/// Number of color attachments of [`Hdr::scene_pass`]: the scene and its bright part
+pub const SCENE_COLOR_COUNT: usize = 2;
+
+/// Sets up a pipeline to draw into [`Hdr::scene_pass`]. Its fragment shader has to write both
+/// outputs (include `hdr.fs.glsl` and call `write_hdr`)
+pub fn scene_pip(pip: &mut rg::PipelineDesc) {
+    pip.color_count = SCENE_COLOR_COUNT as _;
+    for i in 0..SCENE_COLOR_COUNT {
+        pip.colors[i].pixel_format = HDR_FORMAT as u32;
+    }
+}
+
+/// Default gamma of the display
+pub const GAMMA: f32 = 2.2;
+
+/// Maps linear color to `[0, 1]` (component-wise)
+pub fn reinhard(c: [f32; 3]) -> [f32; 3] {
+    self::map(c, |x| x / (x + 1.0))
+}
+
+/// Exposure tone mapping: `1 - exp(-c * exposure)`
+pub fn exposure(c: [f32; 3], exposure: f32) -> [f32; 3] {
+    self::map(c, |x| 1.0 - (-x * exposure).exp())
+}
+
+/// Narkowicz's fit of the ACES filmic curve
+pub fn aces_filmic(c: [f32; 3]) -> [f32; 3] {
+    let (a, b, c_, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+    self::map(c, |x| {
+        let y = (x * (a * x + b)) / (x * (c_ * x + d) + e);
+        y.clamp(0.0, 1.0)
+    })
+}
+
+/// Converts linear color to the display space
+pub fn gamma_correct(c: [f32; 3], gamma: f32) -> [f32; 3] {
+    self::map(c, |x| x.powf(1.0 / gamma))
+}
+
+fn map(c: [f32; 3], f: impl Fn(f32) -> f32) -> [f32; 3] {
+    [f(c[0]), f(c[1]), f(c[2])]
+}
+
+/// Tone mapping operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ToneMap {
+    /// No tone mapping; exposed color is clamped to `[0, 1]`
+    Clamp,
+    #[default]
+    Reinhard,
+    Exposure,
+    Aces,
+}
+
+impl ToneMap {
+    pub const ALL: [Self; 4] = [Self::Clamp, Self::Reinhard, Self::Exposure, Self::Aces];
+
+    /// Variant of `tonemap.fs`
+    pub fn defines(&self) -> &'static [&'static str] {
+        match self {
+            Self::Clamp => &[],
+            Self::Reinhard => &["TONEMAP_REINHARD"],
+            Self::Exposure => &["TONEMAP_EXPOSURE"],
+            Self::Aces => &["TONEMAP_ACES"],
+        }
+    }
+
+    /// The next operator in [`ToneMap::ALL`] (wraps around)
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|x| x == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// CPU reference of the whole resolve pass (tone mapping and gamma correction)
+    pub fn apply(&self, c: [f32; 3], exposure: f32, gamma: f32) -> [f32; 3] {
+        let exposed = self::map(c, |x| x * exposure);
+        let mapped = match self {
+            Self::Clamp => self::map(exposed, |x| x.clamp(0.0, 1.0)),
+            Self::Reinhard => self::reinhard(exposed),
+            Self::Exposure => self::exposure(c, exposure),
+            Self::Aces => self::aces_filmic(exposed),
+        };
+        self::gamma_correct(mapped, gamma)
+    }
+}
+
+/// Floating-point scene render targets and the tone mapping pass
+#[derive(Debug)]
+pub struct Hdr {
+    /// Scene color and its bright part
+    target: MultiRenderTarget,
+    tri: StaticMesh<PostVertex>,
+    /// Tone mapping variants and the blur shader of the bloom
+    shaders: ShaderCache,
+    bloom: Option<Bloom>,
+    tone_map: ToneMap,
+    exposure: f32,
+    gamma: f32,
+    pa: rg::PassAction,
+}
+
+impl Hdr {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            target: RenderTexture2d::builder(w, h).build_multi(&[HDR_FORMAT; SCENE_COLOR_COUNT]),
+            tri: postfx::full_screen_triangle(),
+            shaders: ShaderCache::new(),
+            bloom: None,
+            tone_map: ToneMap::default(),
+            exposure: 1.0,
+            gamma: GAMMA,
+            pa: rg::PassAction::clear([0.0, 0.0, 0.0, 1.0]),
+        }
+    }
+
+    /// Pass to render the scene into
+    pub fn scene_pass(&self) -> rg::Pass {
+        self.target.pass()
+    }
+
+    /// Scene color (0) and its bright part (1)
+    pub fn target(&self) -> &MultiRenderTarget {
+        &self.target
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.target.size()
+    }
+
+    pub fn bloom(&self) -> Option<&Bloom> {
+        self.bloom.as_ref()
+    }
+
+    pub fn bloom_mut(&mut self) -> Option<&mut Bloom> {
+        self.bloom.as_mut()
+    }
+
+    /// Enables or disables bloom. The bloom is resized to the scene
+    pub fn set_bloom(&mut self, bloom: Option<Bloom>) {
+        self.bloom = bloom;
+        let [w, h] = self.size();
+        if let Some(bloom) = self.bloom.as_mut() {
+            bloom.resize(w, h);
+        }
+    }
+
+    pub fn tone_map(&self) -> ToneMap {
+        self.tone_map
+    }
+
+    pub fn set_tone_map(&mut self, tone_map: ToneMap) {
+        self.tone_map = tone_map;
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// Sets the exposure (clamped to be positive)
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure.max(1e-3);
+    }
+
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.gamma = gamma.max(1e-3);
+    }
+
+    /// Recreates the render targets if the size is changed
+    pub fn resize(&mut self, w: u32, h: u32) {
+        self.target.resize(w, h);
+        if let Some(bloom) = self.bloom.as_mut() {
+            bloom.resize(w, h);
+        }
+    }
+
+    /// Hot reloads the tone mapping and blur shaders
+    pub fn hot_reload(&mut self) {
+        self.shaders.hot_reload();
+    }
+
+    /// Variant of `tonemap.fs` for the current settings
+    fn defines(&self) -> Vec<&'static str> {
+        let mut defines = self.tone_map.defines().to_vec();
+        if self.bloom.is_some() {
+            defines.push("BLOOM");
+        }
+        defines
+    }
+
+    /// Shader of the current tone mapping operator
+    pub fn shd_mut(&mut self) -> &mut Shader {
+        let defines = self.defines();
+        self.shaders.get("tonemap", &defines)
+    }
+
+    /// Blurs the bright part if bloom is enabled, then tone maps the scene and draws it to the
+    /// screen of size `(w, h)`
+    pub fn resolve(&mut self, w: u32, h: u32) {
+        let scene = self.target.img(0);
+        let bright = self.target.img(1);
+        let size = self.size();
+
+        // the bright part is bound even without bloom so that all the images are bound
+        let (bloom, bloom_strength) = match self.bloom.as_mut() {
+            Some(bloom) => (
+                bloom.blur(&mut self.shaders, &mut self.tri, bright, size),
+                bloom.strength(),
+            ),
+            None => (bright, 0.0),
+        };
+
+        let uniforms = ToneMapUniforms {
+            exposure: self.exposure,
+            gamma: self.gamma,
+            bloom_strength,
+        };
+        let defines = self.defines();
+
+        rg::begin_default_pass(&self.pa, w as i32, h as i32);
+
+        let shd = self.shaders.get("tonemap", &defines);
+        shd.apply_pip();
+        self.tri.bind_img(scene, 0);
+        self.tri.bind_img(bloom, 1);
+        shd.apply_fs(&uniforms);
+        self.tri.draw_all();
+
+        rg::end_pass();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: [f32; 3], b: [f32; 3]) -> bool {
+        a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-5)
+    }
+
+    #[test]
+    fn reinhard_values() {
+        assert_eq!(self::reinhard([0.0, 1.0, 3.0]), [0.0, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn operators_are_monotonic() {
+        let xs: Vec<f32> = (0..100).map(|i| i as f32 * 0.1).collect();
+        for op in ToneMap::ALL.iter() {
+            let ys: Vec<f32> = xs.iter().map(|x| op.apply([*x; 3], 1.0, 1.0)[0]).collect();
+            assert!(ys.windows(2).all(|w| w[0] <= w[1]), "{:?}", op);
+        }
+    }
+
+    #[test]
+    fn aces_range() {
+        for &x in &[0.0, 0.01, 0.5, 1.0, 10.0, 1e4] {
+            let [y, _, _] = self::aces_filmic([x; 3]);
+            assert!((0.0..=1.0).contains(&y), "{} -> {}", x, y);
+        }
+    }
+
+    #[test]
+    fn exposure_uses_unexposed_input() {
+        let c = [0.5, 1.0, 2.0];
+        assert!(approx(
+            ToneMap::Exposure.apply(c, 2.0, 1.0),
+            self::exposure(c, 2.0)
+        ));
+        assert!(approx(
+            ToneMap::Reinhard.apply(c, 2.0, 1.0),
+            self::reinhard([1.0, 2.0, 4.0])
+        ));
+    }
+
+    #[test]
+    fn gamma_round_trip() {
+        let c = [0.0, 0.2, 0.8];
+        let encoded = self::gamma_correct(c, GAMMA);
+        assert!(approx(self::gamma_correct(encoded, 1.0 / GAMMA), c));
+        assert!(approx(self::gamma_correct(c, 1.0), c));
+    }
+
+    #[test]
+    fn next_wraps_around() {
+        assert_eq!(ToneMap::default(), ToneMap::Reinhard);
+        assert_eq!(ToneMap::Aces.next(), ToneMap::Clamp);
+
+        let mut op = ToneMap::Clamp;
+        for _ in 0..ToneMap::ALL.len() {
+            op = op.next();
+        }
+        assert_eq!(op, ToneMap::Clamp);
+    }
+}
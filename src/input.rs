@@ -0,0 +1,304 @@
+/*!
+Input state on [`rokol::app`] events
+
+Feed events with [`Input::on_event`], query the state in `update` and call [`Input::end_frame`] at
+the end of every frame:
+
+```ignore
+fn event(&mut self, ev: &ra::Event) {
+    self.input.on_event(ev);
+}
+
+fn frame(&mut self) {
+    if self.input.is_down(ra::Key::W) {
+        // ..
+    }
+    self.input.end_frame();
+}
+```
+*/
+
+use std::collections::HashSet;
+
+use rokol::app as ra;
+
+/// Pressed/released state of keys or mouse buttons, identified by their codes
+#[derive(Debug, Clone, Default)]
+pub struct ButtonSet {
+    down: HashSet<u32>,
+    pressed: HashSet<u32>,
+    released: HashSet<u32>,
+}
+
+impl ButtonSet {
+    pub fn press(&mut self, code: u32) {
+        // key repeats are not counted as "just pressed"
+        if self.down.insert(code) {
+            self.pressed.insert(code);
+        }
+    }
+
+    pub fn release(&mut self, code: u32) {
+        if self.down.remove(&code) {
+            self.released.insert(code);
+        }
+    }
+
+    /// Releases everything, e.g. when the window loses focus
+    pub fn release_all(&mut self) {
+        self.released.extend(self.down.drain());
+    }
+
+    /// Down now
+    pub fn is_down(&self, code: u32) -> bool {
+        self.down.contains(&code)
+    }
+
+    /// Pressed in this frame
+    pub fn is_pressed(&self, code: u32) -> bool {
+        self.pressed.contains(&code)
+    }
+
+    /// Released in this frame
+    pub fn is_released(&self, code: u32) -> bool {
+        self.released.contains(&code)
+    }
+
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+}
+
+/// Keyboard and mouse state
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    keys: ButtonSet,
+    mouse: ButtonSet,
+    mouse_pos: [f32; 2],
+    mouse_delta: [f32; 2],
+    scroll: [f32; 2],
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state with an event
+    pub fn on_event(&mut self, ev: &ra::Event) {
+        let ty = ev.type_;
+
+        if ty == ra::EventType::KeyDown as u32 {
+            self.keys.press(ev.key_code);
+        } else if ty == ra::EventType::KeyUp as u32 {
+            self.keys.release(ev.key_code);
+        } else if ty == ra::EventType::MouseDown as u32 {
+            self.mouse.press(ev.mouse_button as u32);
+        } else if ty == ra::EventType::MouseUp as u32 {
+            self.mouse.release(ev.mouse_button as u32);
+        } else if ty == ra::EventType::MouseMove as u32 {
+            self.on_mouse_move([ev.mouse_x, ev.mouse_y], [ev.mouse_dx, ev.mouse_dy]);
+        } else if ty == ra::EventType::MouseScroll as u32 {
+            self.on_scroll([ev.scroll_x, ev.scroll_y]);
+        } else if ty == ra::EventType::Unfocused as u32 {
+            self.keys.release_all();
+            self.mouse.release_all();
+        }
+    }
+
+    /// Mouse movement. `delta` is accumulated until the end of the frame
+    pub fn on_mouse_move(&mut self, pos: [f32; 2], delta: [f32; 2]) {
+        self.mouse_pos = pos;
+        self.mouse_delta[0] += delta[0];
+        self.mouse_delta[1] += delta[1];
+    }
+
+    /// Mouse wheel. Accumulated until the end of the frame
+    pub fn on_scroll(&mut self, delta: [f32; 2]) {
+        self.scroll[0] += delta[0];
+        self.scroll[1] += delta[1];
+    }
+
+    /// Clears per-frame state. Call it at the end of every frame
+    pub fn end_frame(&mut self) {
+        self.keys.end_frame();
+        self.mouse.end_frame();
+        self.mouse_delta = [0.0; 2];
+        self.scroll = [0.0; 2];
+    }
+
+    pub fn keys(&self) -> &ButtonSet {
+        &self.keys
+    }
+
+    pub fn keys_mut(&mut self) -> &mut ButtonSet {
+        &mut self.keys
+    }
+
+    pub fn mouse(&self) -> &ButtonSet {
+        &self.mouse
+    }
+
+    pub fn mouse_mut(&mut self) -> &mut ButtonSet {
+        &mut self.mouse
+    }
+
+    pub fn is_down(&self, key: ra::Key) -> bool {
+        self.keys.is_down(key as u32)
+    }
+
+    pub fn is_pressed(&self, key: ra::Key) -> bool {
+        self.keys.is_pressed(key as u32)
+    }
+
+    pub fn is_released(&self, key: ra::Key) -> bool {
+        self.keys.is_released(key as u32)
+    }
+
+    pub fn is_mouse_down(&self, button: ra::MouseButton) -> bool {
+        self.mouse.is_down(button as u32)
+    }
+
+    pub fn is_mouse_pressed(&self, button: ra::MouseButton) -> bool {
+        self.mouse.is_pressed(button as u32)
+    }
+
+    pub fn is_mouse_released(&self, button: ra::MouseButton) -> bool {
+        self.mouse.is_released(button as u32)
+    }
+
+    /// Mouse position in pixels
+    pub fn mouse_pos(&self) -> [f32; 2] {
+        self.mouse_pos
+    }
+
+    /// Mouse movement in this frame
+    pub fn mouse_delta(&self) -> [f32; 2] {
+        self.mouse_delta
+    }
+
+    /// Mouse wheel movement in this frame
+    pub fn scroll(&self) -> [f32; 2] {
+        self.scroll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(ty: ra::EventType) -> ra::Event {
+        // plain C struct
+        let mut ev: ra::Event = unsafe { std::mem::zeroed() };
+        ev.type_ = ty as u32;
+        ev
+    }
+
+    fn key(ty: ra::EventType, key: ra::Key) -> ra::Event {
+        let mut ev = self::event(ty);
+        ev.key_code = key as u32;
+        ev
+    }
+
+    fn mouse(ty: ra::EventType, button: ra::MouseButton) -> ra::Event {
+        let mut ev = self::event(ty);
+        ev.mouse_button = button as _;
+        ev
+    }
+
+    #[test]
+    fn key_press_and_release() {
+        let mut input = Input::new();
+
+        input.on_event(&key(ra::EventType::KeyDown, ra::Key::W));
+        assert!(input.is_down(ra::Key::W));
+        assert!(input.is_pressed(ra::Key::W));
+        assert!(!input.is_down(ra::Key::Space));
+
+        input.end_frame();
+        assert!(input.is_down(ra::Key::W));
+        assert!(!input.is_pressed(ra::Key::W));
+
+        input.on_event(&key(ra::EventType::KeyUp, ra::Key::W));
+        assert!(!input.is_down(ra::Key::W));
+        assert!(input.is_released(ra::Key::W));
+
+        input.end_frame();
+        assert!(!input.is_released(ra::Key::W));
+    }
+
+    #[test]
+    fn key_repeat_is_not_pressed_again() {
+        let mut input = Input::new();
+        input.on_event(&key(ra::EventType::KeyDown, ra::Key::Space));
+        input.end_frame();
+
+        let mut repeat = key(ra::EventType::KeyDown, ra::Key::Space);
+        repeat.key_repeat = true;
+        input.on_event(&repeat);
+        assert!(input.is_down(ra::Key::Space));
+        assert!(!input.is_pressed(ra::Key::Space));
+    }
+
+    #[test]
+    fn release_without_press_is_ignored() {
+        let mut input = Input::new();
+        input.on_event(&key(ra::EventType::KeyUp, ra::Key::W));
+        assert!(!input.is_released(ra::Key::W));
+    }
+
+    #[test]
+    fn mouse_buttons() {
+        let mut input = Input::new();
+
+        input.on_event(&mouse(ra::EventType::MouseDown, ra::MouseButton::Left));
+        assert!(input.is_mouse_down(ra::MouseButton::Left));
+        assert!(input.is_mouse_pressed(ra::MouseButton::Left));
+
+        input.on_event(&mouse(ra::EventType::MouseUp, ra::MouseButton::Left));
+        assert!(!input.is_mouse_down(ra::MouseButton::Left));
+        assert!(input.is_mouse_released(ra::MouseButton::Left));
+    }
+
+    #[test]
+    fn mouse_motion_and_scroll_accumulate_until_end_frame() {
+        let mut input = Input::new();
+
+        for (x, dx) in [(10.0, 2.0), (13.0, 3.0)].iter() {
+            let mut ev = self::event(ra::EventType::MouseMove);
+            ev.mouse_x = *x;
+            ev.mouse_y = 20.0;
+            ev.mouse_dx = *dx;
+            ev.mouse_dy = -1.0;
+            input.on_event(&ev);
+
+            let mut ev = self::event(ra::EventType::MouseScroll);
+            ev.scroll_y = 1.0;
+            input.on_event(&ev);
+        }
+
+        assert_eq!(input.mouse_pos(), [13.0, 20.0]);
+        assert_eq!(input.mouse_delta(), [5.0, -2.0]);
+        assert_eq!(input.scroll(), [0.0, 2.0]);
+
+        input.end_frame();
+        assert_eq!(input.mouse_pos(), [13.0, 20.0]);
+        assert_eq!(input.mouse_delta(), [0.0, 0.0]);
+        assert_eq!(input.scroll(), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn unfocus_releases_everything() {
+        let mut input = Input::new();
+        input.on_event(&key(ra::EventType::KeyDown, ra::Key::W));
+        input.on_event(&mouse(ra::EventType::MouseDown, ra::MouseButton::Left));
+        input.end_frame();
+
+        input.on_event(&self::event(ra::EventType::Unfocused));
+        assert!(!input.is_down(ra::Key::W));
+        assert!(input.is_released(ra::Key::W));
+        assert!(!input.is_mouse_down(ra::MouseButton::Left));
+        assert!(input.is_mouse_released(ra::MouseButton::Left));
+    }
+}
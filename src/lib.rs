@@ -3,5 +3,16 @@ Rokol Learn OpenGL examples
 */
 
 pub mod apps;
+pub mod assets;
+pub mod bloom;
+pub mod camera;
 pub mod gfx;
+pub mod hdr;
+pub mod input;
+pub mod light;
+pub mod material;
+pub mod postfx;
+pub mod resize;
 pub mod shaders;
+pub mod skybox;
+pub mod time;
@@ -0,0 +1,361 @@
+/*!
+Light sources for Phong and Blinn-Phong shading
+
+[`Lights`] are uploaded as [`LightUniforms`] to the `lit` shader:
+
+```ignore
+let mut lights = Lights::new();
+lights.push(DirLight::new([-0.2, -1.0, -0.3].into(), LightColors::white()));
+lights.push(PointLight::new([1.0, 1.0, 2.0].into(), LightColors::white()));
+
+shd.apply_fs(&lights.uniforms(cam.pos()));
+```
+
+[`Attenuation::factor`] and [`SpotLight::intensity`] are CPU references of `lighting.fs.glsl`.
+*/
+
+use glam::Vec3;
+
+use crate::shaders::LightUniforms;
+
+/// Maximum number of lights in [`LightUniforms`] (array length in `lighting.fs.glsl`)
+pub const MAX_LIGHTS: usize = 8;
+
+/// Kind of the light stored in the `w` component of `light_pos`
+const KIND_DIR: f32 = 0.0;
+const KIND_POINT: f32 = 1.0;
+const KIND_SPOT: f32 = 2.0;
+
+/// Ambient, diffuse and specular colors of a light
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightColors {
+    pub ambient: Vec3,
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+}
+
+impl LightColors {
+    /// `color` with 10% ambient
+    pub fn from_color(color: Vec3) -> Self {
+        Self {
+            ambient: color * 0.1,
+            diffuse: color,
+            specular: color,
+        }
+    }
+
+    pub fn white() -> Self {
+        Self::from_color(Vec3::one())
+    }
+}
+
+/// Distance attenuation `1 / (constant + linear * d + quadratic * d^2)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Default for Attenuation {
+    /// Covers a distance of about 50
+    fn default() -> Self {
+        Self::new(1.0, 0.09, 0.032)
+    }
+}
+
+impl Attenuation {
+    pub fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    /// Intensity at `distance` from the light
+    pub fn factor(&self, distance: f32) -> f32 {
+        1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+    }
+}
+
+/// Light with parallel rays (e.g. the sun)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirLight {
+    /// Direction of the rays
+    pub dir: Vec3,
+    pub colors: LightColors,
+}
+
+impl DirLight {
+    pub fn new(dir: Vec3, colors: LightColors) -> Self {
+        Self { dir, colors }
+    }
+}
+
+/// Light emitting in all directions from a position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub pos: Vec3,
+    pub att: Attenuation,
+    pub colors: LightColors,
+}
+
+impl PointLight {
+    pub fn new(pos: Vec3, colors: LightColors) -> Self {
+        Self {
+            pos,
+            att: Attenuation::default(),
+            colors,
+        }
+    }
+}
+
+/// Light emitting in a cone (e.g. a flashlight)
+///
+/// The intensity fades out from the inner cutoff to the outer cutoff.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    pub pos: Vec3,
+    /// Direction of the cone
+    pub dir: Vec3,
+    /// Angle from the direction in radians
+    pub inner_cutoff: f32,
+    /// Angle from the direction in radians (greater than the inner cutoff)
+    pub outer_cutoff: f32,
+    pub att: Attenuation,
+    pub colors: LightColors,
+}
+
+impl SpotLight {
+    /// Spot light with 12.5 and 17.5 degrees cutoffs
+    pub fn new(pos: Vec3, dir: Vec3, colors: LightColors) -> Self {
+        Self {
+            pos,
+            dir,
+            inner_cutoff: 12.5_f32.to_radians(),
+            outer_cutoff: 17.5_f32.to_radians(),
+            att: Attenuation::default(),
+            colors,
+        }
+    }
+
+    /// Intensity by the cosine of the angle between the direction of the cone and the direction
+    /// from the light to the fragment: one inside the inner cutoff, zero outside the outer cutoff
+    pub fn intensity(&self, cos_theta: f32) -> f32 {
+        let (cos_inner, cos_outer) = (self.inner_cutoff.cos(), self.outer_cutoff.cos());
+        let epsilon = (cos_inner - cos_outer).max(1e-4);
+        ((cos_theta - cos_outer) / epsilon).clamp(0.0, 1.0)
+    }
+}
+
+/// One of the light types
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    Dir(DirLight),
+    Point(PointLight),
+    Spot(SpotLight),
+}
+
+impl From<DirLight> for Light {
+    fn from(light: DirLight) -> Self {
+        Self::Dir(light)
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Self::Point(light)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Self::Spot(light)
+    }
+}
+
+impl Light {
+    pub fn colors(&self) -> &LightColors {
+        match self {
+            Self::Dir(l) => &l.colors,
+            Self::Point(l) => &l.colors,
+            Self::Spot(l) => &l.colors,
+        }
+    }
+
+    /// `(light_pos, light_dir, light_params)` of [`LightUniforms`]
+    fn pack(&self) -> [[f32; 4]; 3] {
+        let v4 = |v: Vec3, w: f32| [v.x, v.y, v.z, w];
+
+        match self {
+            Self::Dir(l) => [
+                v4(Vec3::zero(), KIND_DIR),
+                v4(l.dir.normalize(), 1.0),
+                [1.0, 0.0, 0.0, 0.0],
+            ],
+            Self::Point(l) => [
+                v4(l.pos, KIND_POINT),
+                v4(Vec3::zero(), 1.0),
+                [l.att.constant, l.att.linear, l.att.quadratic, 0.0],
+            ],
+            Self::Spot(l) => [
+                v4(l.pos, KIND_SPOT),
+                v4(l.dir.normalize(), l.inner_cutoff.cos()),
+                [
+                    l.att.constant,
+                    l.att.linear,
+                    l.att.quadratic,
+                    l.outer_cutoff.cos(),
+                ],
+            ],
+        }
+    }
+}
+
+/// Specular reflection model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ShadingModel {
+    /// Angle between the reflected light and the view direction
+    Phong,
+    /// Angle between the normal and the halfway vector
+    #[default]
+    BlinnPhong,
+}
+
+impl ShadingModel {
+    /// The other model
+    pub fn toggle(&self) -> Self {
+        match self {
+            Self::Phong => Self::BlinnPhong,
+            Self::BlinnPhong => Self::Phong,
+        }
+    }
+}
+
+/// Lights of a scene and the shading model
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lights {
+    /// Only the first [`MAX_LIGHTS`] lights are uploaded
+    pub lights: Vec<Light>,
+    pub model: ShadingModel,
+}
+
+impl Lights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a light and returns its index
+    ///
+    /// Lights past [`MAX_LIGHTS`] are kept in [`Lights::lights`] but silently dropped from the
+    /// uniforms (a warning is logged only once, when the first extra light is pushed).
+    pub fn push(&mut self, light: impl Into<Light>) -> usize {
+        if self.lights.len() == MAX_LIGHTS {
+            log::warn!("more than {} lights; the rest are ignored", MAX_LIGHTS);
+        }
+        self.lights.push(light.into());
+        self.lights.len() - 1
+    }
+
+    /// Uniforms of `lighting.fs.glsl` viewed from `view_pos`
+    pub fn uniforms(&self, view_pos: Vec3) -> LightUniforms {
+        let mut u = LightUniforms {
+            view_pos: view_pos.into(),
+            light_count: self.lights.len().min(MAX_LIGHTS) as f32,
+            blinn: match self.model {
+                ShadingModel::Phong => 0.0,
+                ShadingModel::BlinnPhong => 1.0,
+            },
+            ..Default::default()
+        };
+
+        let v4 = |v: Vec3| [v.x, v.y, v.z, 0.0];
+        for (i, light) in self.lights.iter().take(MAX_LIGHTS).enumerate() {
+            let [pos, dir, params] = light.pack();
+            u.light_pos[i] = pos;
+            u.light_dir[i] = dir;
+            u.light_params[i] = params;
+
+            let colors = light.colors();
+            u.light_ambient[i] = v4(colors.ambient);
+            u.light_diffuse[i] = v4(colors.diffuse);
+            u.light_specular[i] = v4(colors.specular);
+        }
+
+        u
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attenuation() {
+        let att = Attenuation::default();
+        assert_eq!(att.factor(0.0), 1.0);
+        assert!(att.factor(10.0) < att.factor(1.0));
+        assert_eq!(Attenuation::new(2.0, 0.0, 0.0).factor(5.0), 0.5);
+    }
+
+    #[test]
+    fn spot_intensity() {
+        let spot = SpotLight::new(Vec3::zero(), -Vec3::unit_y(), LightColors::white());
+        let cos = |deg: f32| deg.to_radians().cos();
+
+        assert_eq!(spot.intensity(cos(0.0)), 1.0);
+        assert_eq!(spot.intensity(cos(12.0)), 1.0);
+        assert_eq!(spot.intensity(cos(20.0)), 0.0);
+
+        let mid = spot.intensity(cos(15.0));
+        assert!(0.0 < mid && mid < 1.0, "{}", mid);
+    }
+
+    #[test]
+    fn uniforms() {
+        let mut lights = Lights::new();
+        assert_eq!(lights.model, ShadingModel::BlinnPhong);
+
+        let ix = lights.push(PointLight::new(
+            [1.0, 2.0, 3.0].into(),
+            LightColors::white(),
+        ));
+        assert_eq!(ix, 0);
+        lights.model = lights.model.toggle();
+
+        let u = lights.uniforms([0.0, 1.0, 0.0].into());
+        assert_eq!(u.light_count, 1.0);
+        assert_eq!(u.blinn, 0.0);
+        assert_eq!(u.view_pos, [0.0, 1.0, 0.0]);
+        assert_eq!(u.light_pos[0], [1.0, 2.0, 3.0, KIND_POINT]);
+        assert_eq!(u.light_diffuse[0], [1.0, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn uniforms_are_capped() {
+        let mut lights = Lights::new();
+        for _ in 0..MAX_LIGHTS + 2 {
+            lights.push(DirLight::new(-Vec3::unit_y(), LightColors::white()));
+        }
+
+        let u = lights.uniforms(Vec3::zero());
+        assert_eq!(u.light_count, MAX_LIGHTS as f32);
+        assert_eq!(u.light_dir[MAX_LIGHTS - 1], [0.0, -1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn uniform_arrays_hold_max_lights() {
+        let u = LightUniforms::default();
+        for len in &[
+            u.light_pos.len(),
+            u.light_dir.len(),
+            u.light_ambient.len(),
+            u.light_diffuse.len(),
+            u.light_specular.len(),
+            u.light_params.len(),
+        ] {
+            assert_eq!(*len, MAX_LIGHTS);
+        }
+    }
+}
@@ -1,17 +1,57 @@
 /*!
 Learn OpenGL Rokol examples
+
+Select an example with the first argument or `ROKOL_APP` environment variable:
+
+```sh
+$ cargo run -- cube
+$ ROKOL_APP=texture cargo run
+$ cargo run -- --list
+```
+
+Press PageDown/PageUp to switch to the next/previous example.
 */
 
 // use glue code for `sokol_app.h` (`sapp`) + `sokol_gfx.h`:
 use rokol::glue::sapp::{run_delayed, Result, Rokol};
 
-// select other `AppData` to swtich application
-use rokol_learn_opengl::apps::CubeApp as AppData;
+use rokol_learn_opengl::apps::{self, AppHost};
+
+/// Environment variable to select an app
+const APP_ENV: &str = "ROKOL_APP";
+
+/// App selected when nothing is specified
+const DEFAULT_APP: &str = "cube";
+
+fn print_apps() {
+    println!("Registered apps:");
+    for app in apps::APPS {
+        println!("  {:<12}{}", app.name, app.desc);
+    }
+}
 
 fn main() -> Result {
     // give implementation to log crate
     env_logger::init();
 
+    let name = match std::env::args().nth(1) {
+        Some(arg) if arg == "--list" || arg == "-l" => {
+            self::print_apps();
+            return Ok(());
+        }
+        Some(arg) => arg,
+        None => std::env::var(APP_ENV).unwrap_or_else(|_| DEFAULT_APP.to_string()),
+    };
+
+    let ix = match apps::find(&name) {
+        Some(ix) => ix,
+        None => {
+            eprintln!("Unknown app: `{}`", name);
+            self::print_apps();
+            std::process::exit(1);
+        }
+    };
+
     let desc = Rokol {
         w: 1280,
         h: 720,
@@ -19,5 +59,5 @@ fn main() -> Result {
         ..Default::default()
     };
 
-    run_delayed(desc, |_rokol| AppData::new())
+    run_delayed(desc, move |_rokol| AppHost::new(ix))
 }
@@ -0,0 +1,320 @@
+/*!
+Materials of the `lit` shader
+
+[`Material`] holds textures by semantic ([`MapKind`]) and scalar parameters, selects the variant
+of the `lit` shader and binds itself before a draw:
+
+```ignore
+let mut material = Material::new();
+material.set_map(MapKind::Diffuse, diffuse).set_map(MapKind::Normal, normal);
+
+let shd = material.apply_pip(&mut self.shaders);
+shd.apply_fs(&self.lights.uniforms(self.cam.pos()));
+shd.apply_vs(&LitVsUniforms::new(model, view_proj));
+material.apply(shd, &mut self.mesh, &self.defaults);
+self.mesh.draw_all();
+```
+
+Missing maps are filled with [`DefaultMaps`].
+*/
+
+use std::rc::Rc;
+
+use rokol::gfx as rg;
+
+use crate::{
+    gfx::{Shader, StaticMesh, Texture2dDrop, TextureBuilder},
+    shaders::{MaterialUniforms, ShaderCache},
+};
+
+/// Semantic of a texture in [`Material`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapKind {
+    Diffuse,
+    Specular,
+    /// Tangent-space normals (load it without sRGB)
+    Normal,
+    Emission,
+}
+
+impl MapKind {
+    pub const ALL: [Self; 4] = [Self::Diffuse, Self::Specular, Self::Normal, Self::Emission];
+
+    /// Image slot in `lit.fs`
+    pub fn slot(&self) -> usize {
+        match self {
+            Self::Diffuse => 0,
+            Self::Specular => 1,
+            Self::Normal => 2,
+            Self::Emission => 3,
+        }
+    }
+
+    /// `#define` of the `lit` variant sampling the map, if it's optional
+    pub fn define(&self) -> Option<&'static str> {
+        match self {
+            Self::Diffuse | Self::Specular => None,
+            Self::Normal => Some("NORMAL_MAP"),
+            Self::Emission => Some("EMISSION_MAP"),
+        }
+    }
+
+    /// RGBA of the 1x1 texture used when the map is missing
+    pub fn default_pixel(&self) -> [u8; 4] {
+        match self {
+            Self::Diffuse | Self::Specular => [255, 255, 255, 255],
+            // +Z in tangent space
+            Self::Normal => [128, 128, 255, 255],
+            Self::Emission => [0, 0, 0, 255],
+        }
+    }
+}
+
+/// Owned or referenced texture
+#[derive(Debug)]
+pub enum TextureRef {
+    Owned(Texture2dDrop),
+    Shared(Rc<Texture2dDrop>),
+    /// Not owned. It has to outlive the material
+    Image(rg::Image),
+}
+
+impl TextureRef {
+    pub fn img(&self) -> rg::Image {
+        match self {
+            Self::Owned(tex) => tex.img(),
+            Self::Shared(tex) => tex.img(),
+            Self::Image(img) => *img,
+        }
+    }
+}
+
+impl From<Texture2dDrop> for TextureRef {
+    fn from(tex: Texture2dDrop) -> Self {
+        Self::Owned(tex)
+    }
+}
+
+impl From<Rc<Texture2dDrop>> for TextureRef {
+    fn from(tex: Rc<Texture2dDrop>) -> Self {
+        Self::Shared(tex)
+    }
+}
+
+impl From<rg::Image> for TextureRef {
+    fn from(img: rg::Image) -> Self {
+        Self::Image(img)
+    }
+}
+
+/// 1x1 textures bound in place of missing maps (see [`MapKind::default_pixel`])
+#[derive(Debug)]
+pub struct DefaultMaps {
+    /// Indexed by [`MapKind::slot`]
+    maps: Vec<Texture2dDrop>,
+}
+
+impl Default for DefaultMaps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DefaultMaps {
+    pub fn new() -> Self {
+        let maps = MapKind::ALL
+            .iter()
+            .map(|kind| {
+                TextureBuilder::from_pixels(&kind.default_pixel(), 1, 1)
+                    .unwrap()
+                    .filter(rg::Filter::Nearest)
+                    .build_texture()
+            })
+            .collect();
+
+        Self { maps }
+    }
+
+    pub fn img(&self, kind: MapKind) -> rg::Image {
+        self.maps[kind.slot()].img()
+    }
+}
+
+/// Textures and parameters of a surface drawn with the `lit` shader
+#[derive(Debug)]
+pub struct Material {
+    /// Indexed by [`MapKind::slot`]
+    maps: [Option<TextureRef>; 4],
+    /// Multiplied to the diffuse map
+    tint: [f32; 4],
+    /// Multiplied to the emission map
+    emission: [f32; 3],
+    /// Specular exponent
+    shininess: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Material {
+    /// No maps, white tint and shininess 32
+    pub fn new() -> Self {
+        Self {
+            maps: [None, None, None, None],
+            tint: [1.0, 1.0, 1.0, 1.0],
+            emission: [1.0, 1.0, 1.0],
+            shininess: 32.0,
+        }
+    }
+
+    pub fn map(&self, kind: MapKind) -> Option<&TextureRef> {
+        self.maps[kind.slot()].as_ref()
+    }
+
+    pub fn set_map(&mut self, kind: MapKind, tex: impl Into<TextureRef>) -> &mut Self {
+        self.maps[kind.slot()] = Some(tex.into());
+        self
+    }
+
+    /// Removes a map and returns it
+    pub fn take_map(&mut self, kind: MapKind) -> Option<TextureRef> {
+        self.maps[kind.slot()].take()
+    }
+
+    pub fn tint(&self) -> [f32; 4] {
+        self.tint
+    }
+
+    /// Sets the color multiplied to the diffuse map
+    pub fn set_tint(&mut self, tint: [f32; 4]) -> &mut Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn shininess(&self) -> f32 {
+        self.shininess
+    }
+
+    /// Sets the specular exponent
+    pub fn set_shininess(&mut self, shininess: f32) -> &mut Self {
+        self.shininess = shininess;
+        self
+    }
+
+    pub fn emission(&self) -> [f32; 3] {
+        self.emission
+    }
+
+    /// Sets the color multiplied to the emission map
+    pub fn set_emission(&mut self, emission: [f32; 3]) -> &mut Self {
+        self.emission = emission;
+        self
+    }
+
+    /// Variant of the `lit` shader for the maps
+    pub fn defines(&self) -> Vec<&'static str> {
+        MapKind::ALL
+            .iter()
+            .filter(|kind| self.map(**kind).is_some())
+            .filter_map(|kind| kind.define())
+            .collect()
+    }
+
+    pub fn uniforms(&self) -> MaterialUniforms {
+        MaterialUniforms {
+            tint: self.tint,
+            emission: self.emission,
+            shininess: self.shininess,
+        }
+    }
+
+    /// Shader variant of the material
+    pub fn shader<'a>(&self, cache: &'a mut ShaderCache) -> &'a mut Shader {
+        cache.get("lit", &self.defines())
+    }
+
+    /// Applies the pipeline of the variant and returns the shader to apply the other uniforms
+    pub fn apply_pip<'a>(&self, cache: &'a mut ShaderCache) -> &'a mut Shader {
+        let shd = self.shader(cache);
+        shd.apply_pip();
+        shd
+    }
+
+    /// Binds the maps to `mesh` and applies the material uniforms. Call it after the pipeline
+    /// of [`Material::shader`] is applied
+    pub fn apply<V>(&self, shd: &Shader, mesh: &mut StaticMesh<V>, defaults: &DefaultMaps) {
+        for kind in MapKind::ALL.iter() {
+            let img = match self.map(*kind) {
+                Some(tex) => tex.img(),
+                None => defaults.img(*kind),
+            };
+            mesh.bind_img(img, kind.slot());
+        }
+
+        shd.apply_fs_at(1, &self.uniforms());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::ffi::CStr;
+
+    use crate::shaders;
+
+    #[test]
+    fn only_optional_maps_add_defines() {
+        let mut material = Material::new();
+        assert!(material.defines().is_empty());
+
+        material
+            .set_map(MapKind::Diffuse, rg::Image::default())
+            .set_map(MapKind::Specular, rg::Image::default());
+        assert!(material.defines().is_empty());
+
+        material.set_map(MapKind::Emission, rg::Image::default());
+        assert_eq!(material.defines(), vec!["EMISSION_MAP"]);
+
+        material.set_map(MapKind::Normal, rg::Image::default());
+        assert_eq!(material.defines(), vec!["NORMAL_MAP", "EMISSION_MAP"]);
+
+        material.take_map(MapKind::Normal);
+        assert_eq!(material.defines(), vec!["EMISSION_MAP"]);
+    }
+
+    #[test]
+    fn slots_match_lit_images() {
+        let mut desc = rg::ShaderDesc::default();
+        (shaders::LIT.mut_shd_desc)(&mut desc);
+
+        let expected = [
+            (MapKind::Diffuse, "diffuse_map"),
+            (MapKind::Specular, "specular_map"),
+            (MapKind::Normal, "normal_map"),
+            (MapKind::Emission, "emission_map"),
+        ];
+        for (kind, name) in &expected {
+            let img = &desc.fs.images[kind.slot()];
+            let img_name = unsafe { CStr::from_ptr(img.name) }.to_str().unwrap();
+            assert_eq!(img_name, *name, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn uniforms_carry_parameters() {
+        let mut material = Material::new();
+        material
+            .set_tint([0.5, 0.25, 1.0, 0.75])
+            .set_emission([2.0, 1.0, 0.0])
+            .set_shininess(64.0);
+
+        let u = material.uniforms();
+        assert_eq!(u.tint, [0.5, 0.25, 1.0, 0.75]);
+        assert_eq!(u.emission, [2.0, 1.0, 0.0]);
+        assert_eq!(u.shininess, 64.0);
+    }
+}
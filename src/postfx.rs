@@ -0,0 +1,236 @@
+/*!
+Post-processing chain
+
+Render the scene into [`PostFx::scene_pass`], then [`PostFx::render`] applies the effects in order
+with ping-pong render textures and draws the result to the screen:
+
+```ignore
+rg::begin_pass(self.postfx.scene_pass(), &self.pa);
+// draw the scene
+rg::end_pass();
+
+self.postfx.render(ra::width() as u32, ra::height() as u32);
+rg::commit();
+```
+*/
+
+use glam::Mat4;
+
+use rokol::gfx as rg;
+
+use crate::{
+    gfx::{RenderTexture2d, Shader, StaticMesh},
+    shaders::{PostFxUniforms, PostVertex, ShaderCache},
+};
+
+/// 3x3 convolution kernel (the first row is the top)
+pub type Kernel = [[f32; 3]; 3];
+
+#[rustfmt::skip]
+pub const SHARPEN: Kernel = [
+    [-1.0, -1.0, -1.0],
+    [-1.0,  9.0, -1.0],
+    [-1.0, -1.0, -1.0],
+];
+
+pub const BLUR: Kernel = [
+    [1.0 / 16.0, 2.0 / 16.0, 1.0 / 16.0],
+    [2.0 / 16.0, 4.0 / 16.0, 2.0 / 16.0],
+    [1.0 / 16.0, 2.0 / 16.0, 1.0 / 16.0],
+];
+
+#[rustfmt::skip]
+pub const EDGE_DETECT: Kernel = [
+    [1.0,  1.0, 1.0],
+    [1.0, -8.0, 1.0],
+    [1.0,  1.0, 1.0],
+];
+
+/// Kernel in the upper-left of a column-major matrix (`m[col][row]` in GLSL)
+fn kernel_mat(k: &Kernel) -> Mat4 {
+    Mat4::from_cols_array_2d(&[
+        [k[0][0], k[1][0], k[2][0], 0.0],
+        [k[0][1], k[1][1], k[2][1], 0.0],
+        [k[0][2], k[1][2], k[2][2], 0.0],
+        [0.0, 0.0, 0.0, 0.0],
+    ])
+}
+
+/// Kind of [`Effect`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EffectKind {
+    Copy,
+    Invert,
+    Grayscale,
+    Kernel(Kernel),
+}
+
+impl EffectKind {
+    /// Variant of `postfx.fs`
+    fn defines(&self) -> &'static [&'static str] {
+        match self {
+            Self::Copy => &[],
+            Self::Invert => &["INVERT"],
+            Self::Grayscale => &["GRAYSCALE"],
+            Self::Kernel(_) => &["KERNEL"],
+        }
+    }
+}
+
+/// Full-screen effect. Its variant of `postfx.fs` is compiled by [`PostFx`] on first use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Effect {
+    kind: EffectKind,
+}
+
+impl Effect {
+    pub const fn new(kind: EffectKind) -> Self {
+        Self { kind }
+    }
+
+    pub const fn invert() -> Self {
+        Self::new(EffectKind::Invert)
+    }
+
+    pub const fn grayscale() -> Self {
+        Self::new(EffectKind::Grayscale)
+    }
+
+    pub const fn kernel(kernel: Kernel) -> Self {
+        Self::new(EffectKind::Kernel(kernel))
+    }
+
+    pub fn kind(&self) -> &EffectKind {
+        &self.kind
+    }
+
+    /// Replaces the kernel. No-op if it's not a kernel effect
+    pub fn set_kernel(&mut self, kernel: Kernel) {
+        if let EffectKind::Kernel(ref mut k) = self.kind {
+            *k = kernel;
+        }
+    }
+
+    /// Draws `tri` with the variant `shd` sampling `src` of `size` into the current pass
+    fn apply(
+        &self,
+        shd: &Shader,
+        tri: &mut StaticMesh<PostVertex>,
+        src: rg::Image,
+        size: [u32; 2],
+    ) {
+        shd.apply_pip();
+        tri.bind_img(src, 0);
+
+        let kernel = match self.kind {
+            EffectKind::Kernel(ref k) => self::kernel_mat(k),
+            _ => Mat4::identity(),
+        };
+        shd.apply_fs(&PostFxUniforms {
+            kernel,
+            texel: [1.0 / size[0] as f32, 1.0 / size[1] as f32],
+        });
+
+        tri.draw_all();
+    }
+}
+
+/// Triangle covering the screen (UV in `[0, 1]` on screen)
+pub fn full_screen_triangle() -> StaticMesh<PostVertex> {
+    StaticMesh::new_16(
+        &[
+            ([-1.0, -1.0], [0.0, 0.0]).into(),
+            ([3.0, -1.0], [2.0, 0.0]).into(),
+            ([-1.0, 3.0], [0.0, 2.0]).into(),
+        ],
+        &[0, 1, 2],
+    )
+}
+
+/// Scene render target and an ordered list of effects
+#[derive(Debug)]
+pub struct PostFx {
+    tri: StaticMesh<PostVertex>,
+    /// Ping-pong render textures. The scene is rendered into the first one
+    targets: [RenderTexture2d; 2],
+    effects: Vec<Effect>,
+    /// Used when there's no effect
+    copy: Effect,
+    /// Variants of `postfx.fs`, kept while switching effects
+    shaders: ShaderCache,
+    pa: rg::PassAction,
+}
+
+impl PostFx {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            tri: self::full_screen_triangle(),
+            targets: [RenderTexture2d::new(w, h), RenderTexture2d::new(w, h)],
+            effects: Vec::new(),
+            copy: Effect::new(EffectKind::Copy),
+            shaders: ShaderCache::new(),
+            pa: rg::PassAction::clear([0.0, 0.0, 0.0, 1.0]),
+        }
+    }
+
+    /// Pass to render the scene into
+    pub fn scene_pass(&self) -> rg::Pass {
+        self.targets[0].pass()
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.targets[0].size()
+    }
+
+    /// Appends an effect to the end of the chain
+    pub fn push(&mut self, effect: Effect) {
+        self.effects.push(effect);
+    }
+
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    /// Effects in the order of application
+    pub fn effects_mut(&mut self) -> &mut Vec<Effect> {
+        &mut self.effects
+    }
+
+    /// Recreates the render textures if the size is changed
+    pub fn resize(&mut self, w: u32, h: u32) {
+        for target in &mut self.targets {
+            target.resize(w, h);
+        }
+    }
+
+    /// Hot reloads the effect shaders
+    pub fn hot_reload(&mut self) {
+        self.shaders.hot_reload();
+    }
+
+    /// Applies the effects to the scene and draws the result to the screen of size `(w, h)`
+    pub fn render(&mut self, w: u32, h: u32) {
+        let size = self.size();
+
+        let (last, rest) = match self.effects.split_last() {
+            Some(x) => x,
+            None => (&self.copy, &[][..]),
+        };
+
+        // index of the target holding the latest image
+        let mut src = 0;
+        for effect in rest {
+            let dst = 1 - src;
+            let shd = self.shaders.get("postfx", effect.kind.defines());
+            rg::begin_pass(self.targets[dst].pass(), &self.pa);
+            effect.apply(shd, &mut self.tri, self.targets[src].img(), size);
+            rg::end_pass();
+            src = dst;
+        }
+
+        let shd = self.shaders.get("postfx", last.kind.defines());
+        rg::begin_default_pass(&self.pa, w as i32, h as i32);
+        last.apply(shd, &mut self.tri, self.targets[src].img(), size);
+        rg::end_pass();
+    }
+}
@@ -0,0 +1,215 @@
+/*!
+Window resize handling
+
+[`ResizeTracker`] follows the framebuffer size from resize events and resizes size-dependent
+resources on change:
+
+```ignore
+fn event(&mut self, ev: &ra::Event) {
+    self.resize.on_event(ev);
+}
+
+fn frame(&mut self) {
+    self.resize.apply(&mut [&mut self.cam.proj, &mut self.postfx]);
+    // ..
+}
+```
+*/
+
+use rokol::app as ra;
+
+use crate::{
+    camera::Projection,
+    gfx::{MultiRenderTarget, RenderTexture2d},
+    hdr::Hdr,
+    postfx::PostFx,
+};
+
+/// Resource that depends on the framebuffer size
+pub trait Resize {
+    fn resize(&mut self, w: u32, h: u32);
+}
+
+impl Resize for Projection {
+    fn resize(&mut self, w: u32, h: u32) {
+        self.set_size(w, h);
+    }
+}
+
+impl Resize for RenderTexture2d {
+    fn resize(&mut self, w: u32, h: u32) {
+        RenderTexture2d::resize(self, w, h);
+    }
+}
+
+impl Resize for MultiRenderTarget {
+    fn resize(&mut self, w: u32, h: u32) {
+        MultiRenderTarget::resize(self, w, h);
+    }
+}
+
+impl Resize for Hdr {
+    fn resize(&mut self, w: u32, h: u32) {
+        Hdr::resize(self, w, h);
+    }
+}
+
+impl Resize for PostFx {
+    fn resize(&mut self, w: u32, h: u32) {
+        PostFx::resize(self, w, h);
+    }
+}
+
+/// Tracks the framebuffer size and reports changes once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResizeTracker {
+    size: [u32; 2],
+    /// Set on size change, cleared by [`ResizeTracker::take_change`]
+    changed: bool,
+}
+
+impl ResizeTracker {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            size: [w, h],
+            changed: false,
+        }
+    }
+
+    /// Tracker of the current window size
+    pub fn from_window() -> Self {
+        Self::new(ra::width() as u32, ra::height() as u32)
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    /// Updates the size with resize events
+    pub fn on_event(&mut self, ev: &ra::Event) {
+        if ev.type_ == ra::EventType::Resized as u32 {
+            self.set_size(ev.framebuffer_width as u32, ev.framebuffer_height as u32);
+        }
+    }
+
+    /// Returns true if the size is changed. Zero sizes (minimized window) are ignored
+    pub fn set_size(&mut self, w: u32, h: u32) -> bool {
+        if w == 0 || h == 0 || self.size == [w, h] {
+            return false;
+        }
+        self.size = [w, h];
+        self.changed = true;
+        true
+    }
+
+    /// Returns the new size if it's changed since the last call
+    pub fn take_change(&mut self) -> Option<[u32; 2]> {
+        if self.changed {
+            self.changed = false;
+            Some(self.size)
+        } else {
+            None
+        }
+    }
+
+    /// Resizes the resources if the size is changed since the last call. Returns true if resized
+    pub fn apply(&mut self, resources: &mut [&mut dyn Resize]) -> bool {
+        let [w, h] = match self.take_change() {
+            Some(size) => size,
+            None => return false,
+        };
+
+        log::debug!("resizing to {}x{}", w, h);
+        for res in resources.iter_mut() {
+            res.resize(w, h);
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records the sizes it's resized to
+    #[derive(Debug, Default)]
+    struct Log(Vec<[u32; 2]>);
+
+    impl Resize for Log {
+        fn resize(&mut self, w: u32, h: u32) {
+            self.0.push([w, h]);
+        }
+    }
+
+    fn resized(w: i32, h: i32) -> ra::Event {
+        // plain C struct
+        let mut ev: ra::Event = unsafe { std::mem::zeroed() };
+        ev.type_ = ra::EventType::Resized as u32;
+        ev.framebuffer_width = w;
+        ev.framebuffer_height = h;
+        ev
+    }
+
+    #[test]
+    fn change_is_taken_once() {
+        let mut tracker = ResizeTracker::new(640, 480);
+        assert_eq!(tracker.take_change(), None);
+
+        assert!(tracker.set_size(800, 600));
+        assert_eq!(tracker.size(), [800, 600]);
+        assert_eq!(tracker.take_change(), Some([800, 600]));
+        assert_eq!(tracker.take_change(), None);
+    }
+
+    #[test]
+    fn same_and_zero_sizes_are_ignored() {
+        let mut tracker = ResizeTracker::new(640, 480);
+        assert!(!tracker.set_size(640, 480));
+        assert!(!tracker.set_size(0, 480));
+        assert!(!tracker.set_size(640, 0));
+        assert_eq!(tracker.size(), [640, 480]);
+        assert_eq!(tracker.take_change(), None);
+    }
+
+    #[test]
+    fn latest_size_wins() {
+        let mut tracker = ResizeTracker::new(640, 480);
+        tracker.set_size(800, 600);
+        tracker.set_size(1024, 768);
+        assert_eq!(tracker.take_change(), Some([1024, 768]));
+    }
+
+    #[test]
+    fn resize_events() {
+        let mut tracker = ResizeTracker::new(640, 480);
+
+        let mut other = self::resized(800, 600);
+        other.type_ = ra::EventType::KeyDown as u32;
+        tracker.on_event(&other);
+        assert_eq!(tracker.take_change(), None);
+
+        tracker.on_event(&self::resized(800, 600));
+        assert_eq!(tracker.take_change(), Some([800, 600]));
+
+        // minimized
+        tracker.on_event(&self::resized(0, 0));
+        assert_eq!(tracker.size(), [800, 600]);
+        assert_eq!(tracker.take_change(), None);
+    }
+
+    #[test]
+    fn apply_resizes_all_once() {
+        let mut tracker = ResizeTracker::new(640, 480);
+        let (mut a, mut b) = (Log::default(), Log::default());
+
+        assert!(!tracker.apply(&mut [&mut a, &mut b]));
+
+        tracker.set_size(800, 600);
+        assert!(tracker.apply(&mut [&mut a, &mut b]));
+        assert!(!tracker.apply(&mut [&mut a, &mut b]));
+
+        assert_eq!(a.0, vec![[800, 600]]);
+        assert_eq!(b.0, vec![[800, 600]]);
+    }
+}
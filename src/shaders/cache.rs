@@ -0,0 +1,147 @@
+/*!
+Shader variants keyed by `#define`s
+
+```ignore
+let mut cache = ShaderCache::new();
+let shd = cache.get("lit", &["NORMAL_MAP"]);
+shd.apply_pip();
+```
+*/
+
+use std::collections::HashMap;
+
+use crate::gfx::Shader;
+
+use super::{ShaderSpec, BUILTINS};
+
+/// Compiles a [`Shader`] per distinct set of `#define`s on first request and reuses it afterwards
+///
+/// All the variants are released when the cache is dropped.
+pub struct ShaderCache {
+    /// Shader name -> spec to build the variants from
+    specs: HashMap<&'static str, ShaderSpec>,
+    /// (shader name, sorted defines) -> variant
+    variants: HashMap<(String, Vec<String>), Shader>,
+}
+
+impl std::fmt::Debug for ShaderCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShaderCache")
+            .field("specs", &self.specs.keys().collect::<Vec<_>>())
+            .field("variants", &self.variants.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Default for ShaderCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShaderCache {
+    /// Cache with the [`BUILTINS`] registered
+    pub fn new() -> Self {
+        let mut cache = Self::empty();
+        for spec in BUILTINS {
+            cache.register(*spec);
+        }
+        cache
+    }
+
+    /// Cache with no shader registered
+    pub fn empty() -> Self {
+        Self {
+            specs: HashMap::new(),
+            variants: HashMap::new(),
+        }
+    }
+
+    /// Registers (or replaces) a shader by [`ShaderSpec::name`]. Existing variants of the shader
+    /// are released
+    pub fn register(&mut self, spec: ShaderSpec) {
+        self.variants.retain(|(n, _), _| n != spec.name);
+        self.specs.insert(spec.name, spec);
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.specs.contains_key(name)
+    }
+
+    /// Returns the variant of shader `name` with `defines`, compiling it on first request
+    ///
+    /// The order and duplicates of `defines` don't matter. Panics if `name` is not registered.
+    pub fn get(&mut self, name: &str, defines: &[&str]) -> &mut Shader {
+        let key = (name.to_string(), self::normalize(defines));
+
+        if !self.variants.contains_key(&key) {
+            let spec = self
+                .specs
+                .get(name)
+                .unwrap_or_else(|| panic!("shader `{}` is not registered", name));
+            let defines: Vec<&str> = key.1.iter().map(|d| d.as_str()).collect();
+            let shd = spec.build(&defines);
+            self.variants.insert(key.clone(), shd);
+        }
+
+        self.variants.get_mut(&key).unwrap()
+    }
+
+    /// Number of compiled variants
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    /// Releases all the variants
+    pub fn clear(&mut self) {
+        self.variants.clear();
+    }
+
+    /// Calls [`Shader::hot_reload`] for every variant. Returns true if any of them is reloaded
+    pub fn hot_reload(&mut self) -> bool {
+        let mut any = false;
+        for shd in self.variants.values_mut() {
+            any |= shd.hot_reload();
+        }
+        any
+    }
+}
+
+/// Sorts and deduplicates defines
+fn normalize(defines: &[&str]) -> Vec<String> {
+    let mut defines: Vec<String> = defines.iter().map(|d| d.trim().to_string()).collect();
+    defines.sort();
+    defines.dedup();
+    defines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_sorts_and_dedups() {
+        assert_eq!(
+            self::normalize(&["B", " A", "B", "A "]),
+            vec!["A".to_string(), "B".to_string()]
+        );
+        assert!(self::normalize(&[]).is_empty());
+    }
+
+    #[test]
+    fn builtins_are_registered() {
+        let mut cache = ShaderCache::empty();
+        assert!(!cache.is_registered("lit"));
+
+        for spec in BUILTINS {
+            cache.register(*spec);
+        }
+        assert!(BUILTINS.iter().all(|spec| cache.is_registered(spec.name)));
+        assert!(!cache.is_registered("unknown"));
+        assert!(cache.is_empty());
+    }
+}
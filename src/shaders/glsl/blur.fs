@@ -0,0 +1,32 @@
+#version 330
+
+// one direction of a separable Gaussian blur (weights are generated in `bloom.rs`)
+
+uniform sampler2D tex;
+
+// weights[i / 4][i % 4] is the weight of the i-th texel from the center
+uniform vec4 weights[4];
+// texel step in the blur direction
+uniform vec2 dir;
+uniform float radius;
+
+in vec2 fs_uv;
+
+out vec4 frag_color;
+
+float weight(int i) {
+    return weights[i / 4][i % 4];
+}
+
+void main() {
+    vec3 sum = texture(tex, fs_uv).rgb * weight(0);
+
+    int n = int(radius);
+    for (int i = 1; i <= n; i++) {
+        vec2 offset = dir * float(i);
+        sum += texture(tex, fs_uv + offset).rgb * weight(i);
+        sum += texture(tex, fs_uv - offset).rgb * weight(i);
+    }
+
+    frag_color = vec4(sum, 1.0);
+}
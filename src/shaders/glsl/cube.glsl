@@ -0,0 +1,35 @@
+// Textured cube (sokol-shdc annotated)
+
+@vs vs
+uniform vs_params {
+    mat4 mvp;
+};
+
+layout(location=0) in vec3 vs_pos;
+layout(location=1) in vec4 vs_color;
+layout(location=2) in vec2 vs_uv;
+
+out vec4 fs_color;
+out vec2 fs_uv;
+
+void main() {
+    gl_Position = mvp * vec4(vs_pos, 1.0);
+    fs_color = vs_color;
+    fs_uv = vs_uv;
+}
+@end
+
+@fs fs
+uniform sampler2D tex;
+
+in vec4 fs_color;
+in vec2 fs_uv;
+
+out vec4 out_color;
+
+void main() {
+    out_color = texture(tex, fs_uv) * fs_color;
+}
+@end
+
+@program cube vs fs
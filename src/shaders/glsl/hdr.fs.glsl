@@ -0,0 +1,15 @@
+// outputs of fragment shaders drawing into `Hdr::scene_pass`: color and its bright part (bloom)
+
+layout(location=0) out vec4 frag_color;
+layout(location=1) out vec4 bright_color;
+
+#ifndef BLOOM_THRESHOLD
+#define BLOOM_THRESHOLD 1.0
+#endif
+
+void write_hdr(vec3 color, float alpha) {
+    frag_color = vec4(color, alpha);
+
+    float brightness = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    bright_color = brightness > BLOOM_THRESHOLD ? vec4(color, 1.0) : vec4(0.0, 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,71 @@
+// lights of `light.rs` (`LightUniforms`) and Phong / Blinn-Phong shading
+
+// arrays of `MAX_LIGHTS` (8) elements (no macro so that the declarations can be validated)
+
+// xyz: position, w: kind (0: directional, 1: point, 2: spot)
+uniform vec4 light_pos[8];
+// xyz: direction of the rays, w: cosine of the inner cutoff
+uniform vec4 light_dir[8];
+uniform vec4 light_ambient[8];
+uniform vec4 light_diffuse[8];
+uniform vec4 light_specular[8];
+// x: constant, y: linear, z: quadratic, w: cosine of the outer cutoff
+uniform vec4 light_params[8];
+
+uniform vec3 view_pos;
+uniform float light_count;
+// 0: Phong, 1: Blinn-Phong
+uniform float blinn;
+
+// `l`: direction from the fragment to the light
+float specular_factor(vec3 l, vec3 normal, vec3 view_dir, float shininess) {
+    if (blinn > 0.5) {
+        vec3 halfway = normalize(l + view_dir);
+        return pow(max(dot(normal, halfway), 0.0), shininess);
+    } else {
+        vec3 reflected = reflect(-l, normal);
+        return pow(max(dot(view_dir, reflected), 0.0), shininess);
+    }
+}
+
+vec3 shade_light(int i, vec3 pos, vec3 normal, vec3 view_dir, vec3 diffuse, vec3 specular,
+                 float shininess) {
+    float kind = light_pos[i].w;
+    vec4 params = light_params[i];
+
+    vec3 l;
+    float att = 1.0;
+    float spot = 1.0;
+
+    if (kind < 0.5) {
+        l = normalize(-light_dir[i].xyz);
+    } else {
+        vec3 to_light = light_pos[i].xyz - pos;
+        float d = length(to_light);
+        l = to_light / d;
+        att = 1.0 / (params.x + params.y * d + params.z * d * d);
+
+        if (kind > 1.5) {
+            float cos_theta = dot(-l, light_dir[i].xyz);
+            float cos_inner = light_dir[i].w;
+            float cos_outer = params.w;
+            spot = clamp((cos_theta - cos_outer) / max(cos_inner - cos_outer, 1e-4), 0.0, 1.0);
+        }
+    }
+
+    vec3 a = light_ambient[i].rgb * diffuse;
+    vec3 d = light_diffuse[i].rgb * max(dot(normal, l), 0.0) * diffuse;
+    vec3 s = light_specular[i].rgb * specular_factor(l, normal, view_dir, shininess) * specular;
+
+    return (a + (d + s) * spot) * att;
+}
+
+// sum of all the lights
+vec3 shade(vec3 pos, vec3 normal, vec3 view_dir, vec3 diffuse, vec3 specular, float shininess) {
+    vec3 color = vec3(0.0);
+    int n = int(light_count);
+    for (int i = 0; i < n; i++) {
+        color += shade_light(i, pos, normal, view_dir, diffuse, specular, shininess);
+    }
+    return color;
+}
@@ -0,0 +1,59 @@
+#version 330
+
+// Variants: NORMAL_MAP, EMISSION_MAP (see `material.rs`)
+
+#include "hdr.fs.glsl"
+#include "lighting.fs.glsl"
+
+uniform sampler2D diffuse_map;
+uniform sampler2D specular_map;
+// tangent-space normals
+uniform sampler2D normal_map;
+uniform sampler2D emission_map;
+
+uniform vec4 tint;
+// multiplied to the emission map
+uniform vec3 emission;
+uniform float shininess;
+
+in vec3 fs_pos;
+in vec3 fs_normal;
+in vec2 fs_uv;
+
+#ifdef NORMAL_MAP
+// tangent frame from screen-space derivatives, so that vertices don't need tangents
+mat3 cotangent_frame(vec3 n, vec3 p, vec2 uv) {
+    vec3 dp1 = dFdx(p);
+    vec3 dp2 = dFdy(p);
+    vec2 duv1 = dFdx(uv);
+    vec2 duv2 = dFdy(uv);
+
+    vec3 dp2perp = cross(dp2, n);
+    vec3 dp1perp = cross(n, dp1);
+    vec3 t = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 b = dp2perp * duv1.y + dp1perp * duv2.y;
+
+    float inv_max = inversesqrt(max(dot(t, t), dot(b, b)));
+    return mat3(t * inv_max, b * inv_max, n);
+}
+#endif
+
+void main() {
+    vec3 normal = normalize(fs_normal);
+#ifdef NORMAL_MAP
+    vec3 tangent_normal = texture(normal_map, fs_uv).xyz * 2.0 - 1.0;
+    normal = normalize(cotangent_frame(normal, fs_pos, fs_uv) * tangent_normal);
+#endif
+
+    vec3 view_dir = normalize(view_pos - fs_pos);
+
+    vec4 diffuse = texture(diffuse_map, fs_uv) * tint;
+    vec3 specular = texture(specular_map, fs_uv).rgb;
+
+    vec3 color = shade(fs_pos, normal, view_dir, diffuse.rgb, specular, shininess);
+#ifdef EMISSION_MAP
+    color += texture(emission_map, fs_uv).rgb * emission;
+#endif
+
+    write_hdr(color, diffuse.a);
+}
@@ -0,0 +1,23 @@
+#version 330
+
+uniform mat4 model;
+uniform mat4 view_proj;
+// inverse transpose of the model matrix (there's no `mat3` uniform)
+uniform mat4 normal_mat;
+
+layout(location=0) in vec3 vs_pos;
+layout(location=1) in vec3 vs_normal;
+layout(location=2) in vec2 vs_uv;
+
+out vec3 fs_pos;
+out vec3 fs_normal;
+out vec2 fs_uv;
+
+void main() {
+    vec4 world = model * vec4(vs_pos, 1.0);
+    gl_Position = view_proj * world;
+
+    fs_pos = world.xyz;
+    fs_normal = mat3(normal_mat) * vs_normal;
+    fs_uv = vs_uv;
+}
@@ -0,0 +1,36 @@
+#version 330
+
+// Variants: INVERT, GRAYSCALE, KERNEL, or copy if none is defined
+
+uniform sampler2D tex;
+
+// 3x3 convolution kernel in the upper-left of the matrix (`KERNEL` only)
+uniform mat4 kernel;
+// size of a texel in UV coordinates (`KERNEL` only)
+uniform vec2 texel;
+
+in vec2 fs_uv;
+
+out vec4 frag_color;
+
+void main() {
+#if defined(INVERT)
+    frag_color = vec4(1.0 - texture(tex, fs_uv).rgb, 1.0);
+#elif defined(GRAYSCALE)
+    vec3 color = texture(tex, fs_uv).rgb;
+    float lum = 0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b;
+    frag_color = vec4(vec3(lum), 1.0);
+#elif defined(KERNEL)
+    vec3 sum = vec3(0.0);
+    for (int row = 0; row < 3; row++) {
+        for (int col = 0; col < 3; col++) {
+            // the first row is the top
+            vec2 offset = vec2(float(col - 1), float(1 - row)) * texel;
+            sum += kernel[col][row] * texture(tex, fs_uv + offset).rgb;
+        }
+    }
+    frag_color = vec4(sum, 1.0);
+#else
+    frag_color = texture(tex, fs_uv);
+#endif
+}
@@ -0,0 +1,11 @@
+#version 330
+
+uniform samplerCube skybox;
+
+in vec3 fs_dir;
+
+out vec4 frag_color;
+
+void main() {
+    frag_color = texture(skybox, fs_dir);
+}
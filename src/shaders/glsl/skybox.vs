@@ -0,0 +1,14 @@
+#version 330
+
+// view matrix without translation
+uniform mat4 view_proj;
+
+layout(location=0) in vec3 vs_pos;
+
+out vec3 fs_dir;
+
+void main() {
+    // z = w: the depth is always 1.0 (the farthest)
+    gl_Position = (view_proj * vec4(vs_pos, 1.0)).xyww;
+    fs_dir = vs_pos;
+}
@@ -1,9 +1,6 @@
 #version 330
 
-uniform sampler2D tex;
-
-in vec4 fs_color;
-in vec2 fs_uv;
+#include "textured.fs.glsl"
 
 out vec4 frag_color;
 
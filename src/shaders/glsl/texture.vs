@@ -1,11 +1,6 @@
 #version 330
 
-layout(location=0) in vec3 vs_pos;
-layout(location=1) in vec4 vs_color;
-layout(location=2) in vec2 vs_uv;
-
-out vec4 fs_color;
-out vec2 fs_uv;
+#include "textured.vs.glsl"
 
 void main() {
     gl_Position = vec4(vs_pos, 1.0);
@@ -0,0 +1,6 @@
+// varyings and texture of `textured.vs.glsl`
+
+uniform sampler2D tex;
+
+in vec4 fs_color;
+in vec2 fs_uv;
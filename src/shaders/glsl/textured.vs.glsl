@@ -1,6 +1,4 @@
-#version 330
-
-uniform mat4 mvp;
+// (position, color, uv) vertex inputs and varyings
 
 layout(location=0) in vec3 vs_pos;
 layout(location=1) in vec4 vs_color;
@@ -8,9 +6,3 @@ layout(location=2) in vec2 vs_uv;
 
 out vec4 fs_color;
 out vec2 fs_uv;
-
-void main() {
-    gl_Position = mvp * vec4(vs_pos, 1.0);
-    fs_color = vs_color;
-    fs_uv = vs_uv;
-}
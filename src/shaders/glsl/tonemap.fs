@@ -0,0 +1,47 @@
+#version 330
+
+// Variants: TONEMAP_REINHARD, TONEMAP_EXPOSURE, TONEMAP_ACES, or clamp if none is defined
+// (keep in sync with `hdr.rs`), and BLOOM
+
+uniform sampler2D hdr;
+// blurred bright part of the scene
+uniform sampler2D bloom;
+
+uniform float exposure;
+uniform float gamma;
+uniform float bloom_strength;
+
+in vec2 fs_uv;
+
+out vec4 frag_color;
+
+// Narkowicz's fit of the ACES filmic curve
+vec3 aces(vec3 x) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+}
+
+void main() {
+    vec3 color = texture(hdr, fs_uv).rgb;
+#ifdef BLOOM
+    color += texture(bloom, fs_uv).rgb * bloom_strength;
+#endif
+
+#if defined(TONEMAP_REINHARD)
+    color *= exposure;
+    color = color / (color + vec3(1.0));
+#elif defined(TONEMAP_EXPOSURE)
+    color = vec3(1.0) - exp(-color * exposure);
+#elif defined(TONEMAP_ACES)
+    color = aces(color * exposure);
+#else
+    color = clamp(color * exposure, 0.0, 1.0);
+#endif
+
+    color = pow(color, vec3(1.0 / gamma));
+    frag_color = vec4(color, 1.0);
+}
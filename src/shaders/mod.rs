@@ -2,106 +2,372 @@
 Shaders
 
 Be sure to set uniform names (or else, it fails or the order of uniforms would be broken).
+[`validate_builtins`] checks them against the GLSL sources, and debug builds log mismatches on
+shader creation. Programs in [sokol-shdc](shdc) annotated files ([`ShaderSource::Shdc`]) set
+them from the source.
 */
 
-#![allow(unused)]
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
 
 use rokol::gfx::{self as rg, BakedResource};
 
-use crate::gfx::Shader;
+use crate::gfx::{self, HotReload, Shader, Vertex};
 
-/// Shorthand for specifying shader files
-macro_rules! def_shd {
-    ($file:expr) => {
-        embed_shd!(
-            concat!("glsl/", $file, ".vs"),
-            concat!("glsl/", $file, ".fs"),
-        )
-    };
+pub mod cache;
+pub mod preprocess;
+pub mod reflect;
+pub mod shdc;
+
+pub use cache::ShaderCache;
+
+/// GLSL files embedded for release build (including sokol-shdc annotated files)
+static GLSL_FILES: &[(&str, &str)] = &[
+    ("triangle.vs", include_str!("glsl/triangle.vs")),
+    ("triangle.fs", include_str!("glsl/triangle.fs")),
+    ("texture.vs", include_str!("glsl/texture.vs")),
+    ("texture.fs", include_str!("glsl/texture.fs")),
+    ("cube.glsl", include_str!("glsl/cube.glsl")),
+    ("postfx.vs", include_str!("glsl/postfx.vs")),
+    ("postfx.fs", include_str!("glsl/postfx.fs")),
+    ("skybox.vs", include_str!("glsl/skybox.vs")),
+    ("skybox.fs", include_str!("glsl/skybox.fs")),
+    ("tonemap.vs", include_str!("glsl/tonemap.vs")),
+    ("tonemap.fs", include_str!("glsl/tonemap.fs")),
+    ("blur.vs", include_str!("glsl/blur.vs")),
+    ("blur.fs", include_str!("glsl/blur.fs")),
+    ("lit.vs", include_str!("glsl/lit.vs")),
+    ("lit.fs", include_str!("glsl/lit.fs")),
+    (
+        "fullscreen.vs.glsl",
+        include_str!("glsl/fullscreen.vs.glsl"),
+    ),
+    ("hdr.fs.glsl", include_str!("glsl/hdr.fs.glsl")),
+    ("lighting.fs.glsl", include_str!("glsl/lighting.fs.glsl")),
+    ("textured.vs.glsl", include_str!("glsl/textured.vs.glsl")),
+    ("textured.fs.glsl", include_str!("glsl/textured.fs.glsl")),
+];
+
+/// Directory of the GLSL files on debug build
+fn glsl_dir() -> PathBuf {
+    PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("src/shaders/glsl")
+}
+
+/// Loads a GLSL file, dynamically on debug build, statically on release build
+fn load_glsl(name: &str) -> Option<String> {
+    if cfg!(debug_assertions) {
+        std::fs::read_to_string(self::glsl_dir().join(name)).ok()
+    } else {
+        GLSL_FILES
+            .iter()
+            .find(|(file, _)| *file == name)
+            .map(|(_, src)| src.to_string())
+    }
 }
 
-/// Loads shader files, dynamically on debug build, staticlly on release build
-macro_rules! embed_shd {
-    ($vs:expr, $fs:expr,) => {
+/// Preprocessed `<name>.vs` and `<name>.fs`
+struct ShdSource {
+    /// Null-terminated vertex and fragment shader sources
+    src: [String; 2],
+    /// File indices of each stage (see [`preprocess::Output::file_indices`])
+    file_indices: [String; 2],
+    /// Source files (on debug build)
+    paths: Vec<PathBuf>,
+}
+
+/// Preprocesses `<name>.vs` and `<name>.fs`
+fn load_shd(name: &str, defines: &[&str]) -> Result<ShdSource, String> {
+    let mut shd = ShdSource {
+        src: [String::new(), String::new()],
+        file_indices: [String::new(), String::new()],
+        paths: Vec::new(),
+    };
+
+    for (i, ext) in ["vs", "fs"].iter().enumerate() {
+        let file = format!("{}.{}", name, ext);
+        let out = preprocess::preprocess(&file, defines, &mut self::load_glsl)
+            .map_err(|err| err.to_string())?;
+
+        shd.file_indices[i] = out.file_indices();
+
         if cfg!(debug_assertions) {
-            // debug: dynamically load the shader files
-            let dir = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
-                .join("src/shaders");
-            let mut v = std::fs::read_to_string(dir.join($vs)).unwrap();
-            v.push('\0');
-            let mut f = std::fs::read_to_string(dir.join($fs)).unwrap();
-            f.push('\0');
-            [v, f]
-        } else {
-            // release: statically load the shader files
-            [
-                concat!(include_str!($vs), "\0").to_string(),
-                concat!(include_str!($fs), "\0").to_string(),
-            ]
+            let dir = self::glsl_dir();
+            shd.paths.extend(out.files.iter().map(|f| dir.join(f)));
         }
-    };
+
+        shd.src[i] = out.src;
+        shd.src[i].push('\0');
+    }
+
+    shd.paths.sort();
+    shd.paths.dedup();
+
+    Ok(shd)
 }
 
-/// Generates [`Shader`]
+/// Generates [`Shader`] from `<name>.vs` and `<name>.fs` in `glsl/`
+///
+/// * `defines`: `NAME` or `NAME=VALUE` inserted after `#version`
 fn gen(
-    vs_fs: &[impl AsRef<str>; 2],
-    mut_shd_desc: impl Fn(&mut rg::ShaderDesc),
+    name: &str,
+    defines: &[&str],
+    mut_shd_desc: impl Fn(&mut rg::ShaderDesc) + 'static,
     pip_desc: &mut rg::PipelineDesc,
 ) -> Shader {
-    let mut shd_desc = unsafe { rokol::gfx::shader_desc(vs_fs[0].as_ref(), vs_fs[1].as_ref()) };
-    mut_shd_desc(&mut shd_desc);
+    let ShdSource {
+        src: [vs, fs],
+        file_indices,
+        paths,
+    } = self::load_shd(name, defines).unwrap_or_else(|err| panic!("{}", err));
+
+    // shared with the loader so that compile errors refer to the latest files
+    let file_indices = Rc::new(RefCell::new(file_indices));
+
+    let pip_desc = *pip_desc;
+    let shd_name = name.to_string();
+    let build = {
+        let file_indices = Rc::clone(&file_indices);
+        move |vs: &str, fs: &str| {
+            let mut shd_desc = unsafe { rokol::gfx::shader_desc(vs, fs) };
+            mut_shd_desc(&mut shd_desc);
 
-    let shd = rg::Shader::create(&shd_desc);
+            if cfg!(debug_assertions) {
+                if let Err(errs) = reflect::validate(vs, fs, &shd_desc, &pip_desc.layout) {
+                    for err in errs {
+                        log::warn!("shader `{}`: {}", shd_name, err);
+                    }
+                }
+            }
+
+            let shd = rg::Shader::create(&shd_desc);
+
+            if rg::query_shader_state(shd) != rg::ResourceState::Valid {
+                // sokol logs the GLSL compile errors with the file indices of `#line` directives
+                let [vs_files, fs_files] = &*file_indices.borrow();
+                log::error!(
+                    "shader `{}` failed to compile (file indices: vs [{}], fs [{}])",
+                    shd_name,
+                    vs_files,
+                    fs_files
+                );
+            }
+
+            let mut pip_desc = pip_desc;
+            pip_desc.shader = shd;
+            let pip = rg::Pipeline::create(&pip_desc);
+
+            (shd, pip)
+        }
+    };
 
-    pip_desc.shader = shd;
-    let pip = rg::Pipeline::create(&pip_desc);
+    let (shd, pip) = build(&vs, &fs);
+    let shader = Shader::new(shd, pip);
 
-    Shader::new(shd, pip)
+    if cfg!(debug_assertions) {
+        let name = name.to_string();
+        let defines: Vec<String> = defines.iter().map(|d| d.to_string()).collect();
+        let load = move || {
+            let defines: Vec<&str> = defines.iter().map(|d| d.as_str()).collect();
+            let shd = self::load_shd(&name, &defines)?;
+            *file_indices.borrow_mut() = shd.file_indices;
+            Ok((shd.src, shd.paths))
+        };
+        shader.with_hot_reload(HotReload::new(paths, Box::new(load), Box::new(build)))
+    } else {
+        shader
+    }
 }
 
-/// Sets image type
-macro_rules! img_type {
-    ($name:expr,$ty:expr) => {
-        rg::ShaderImageDesc {
-            name: concat!($name, "\0").as_ptr() as *const _,
-            image_type: $ty as u32,
-            ..Default::default()
+/// Loads a program in a sokol-shdc annotated GLSL file. Also returns the path of the file (on debug
+/// build)
+fn load_shdc(file: &str, program: &str) -> Result<(shdc::ShdcProgram, Vec<PathBuf>), String> {
+    let src = self::load_glsl(file).ok_or_else(|| format!("{}: file not found", file))?;
+    let shdc_file = shdc::parse(&src).map_err(|err| format!("{}: {}", file, err))?;
+
+    let prog = shdc_file
+        .programs
+        .into_iter()
+        .find(|p| p.name == program)
+        .ok_or_else(|| format!("{}: no program `{}`", file, program))?;
+
+    let paths = if cfg!(debug_assertions) {
+        vec![self::glsl_dir().join(file)]
+    } else {
+        Vec::new()
+    };
+
+    Ok((prog, paths))
+}
+
+/// Generates [`Shader`] from a program in a [sokol-shdc](shdc) annotated GLSL file in `glsl/`
+///
+/// Uniform blocks and images are set from the source, so only the pipeline has to be described.
+fn gen_shdc(
+    file: &str,
+    program: &str,
+    mut_shd_desc: impl Fn(&mut rg::ShaderDesc) + 'static,
+    pip_desc: &mut rg::PipelineDesc,
+) -> Shader {
+    let (prog, paths) = self::load_shdc(file, program).unwrap_or_else(|err| panic!("{}", err));
+
+    // shared with the loader so that the builder sees the latest uniform blocks and images
+    let prog = Rc::new(RefCell::new(prog));
+
+    let pip_desc = *pip_desc;
+    let build = {
+        let prog = Rc::clone(&prog);
+        let file = file.to_string();
+        move |vs: &str, fs: &str| {
+            let prog = prog.borrow();
+            let mut shd_desc = prog.shader_desc();
+            mut_shd_desc(&mut shd_desc);
+
+            if cfg!(debug_assertions) {
+                if let Err(errs) = reflect::validate(vs, fs, &shd_desc, &pip_desc.layout) {
+                    for err in errs {
+                        log::warn!("shader `{}` in `{}`: {}", prog.name, file, err);
+                    }
+                }
+            }
+
+            let shd = rg::Shader::create(&shd_desc);
+
+            let mut pip_desc = pip_desc;
+            pip_desc.shader = shd;
+            let pip = rg::Pipeline::create(&pip_desc);
+
+            (shd, pip)
         }
     };
+
+    let (shd, pip) = {
+        let p = prog.borrow();
+        build(&p.vs.src, &p.fs.src)
+    };
+    let shader = Shader::new(shd, pip);
+
+    if cfg!(debug_assertions) {
+        let file = file.to_string();
+        let program = program.to_string();
+        let load = move || {
+            let (new_prog, paths) = self::load_shdc(&file, &program)?;
+            let src = [new_prog.vs.src.clone(), new_prog.fs.src.clone()];
+            *prog.borrow_mut() = new_prog;
+            Ok((src, paths))
+        };
+        shader.with_hot_reload(HotReload::new(paths, Box::new(load), Box::new(build)))
+    } else {
+        shader
+    }
 }
 
-/// Single-value uniform block
-macro_rules! ub {
-    ($name:expr, $uniform_ty:expr, $size_ty:ty) => {{
-        let mut block = rg::ShaderUniformBlockDesc::default();
+/// GLSL sources of a [`ShaderSpec`] in `glsl/`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderSource {
+    /// `<name>.vs` and `<name>.fs`
+    Files,
+    /// Program `<name>` in a sokol-shdc annotated file. It has no variants
+    Shdc(&'static str),
+}
 
-        block.uniforms[0] = rg::ShaderUniformDesc {
-            name: concat!($name, "\0").as_ptr() as *const _,
-            type_: $uniform_ty as u32,
-            ..Default::default()
+/// Descriptors of a shader, which can be validated without creating GPU resources
+#[derive(Clone, Copy)]
+pub struct ShaderSpec {
+    /// File name stem or sokol-shdc program name (see [`ShaderSource`])
+    pub name: &'static str,
+    pub source: ShaderSource,
+    pub mut_shd_desc: fn(&mut rg::ShaderDesc),
+    pub pip_desc: fn() -> rg::PipelineDesc,
+}
+
+impl std::fmt::Debug for ShaderSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShaderSpec")
+            .field("name", &self.name)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl ShaderSpec {
+    /// Generates the [`Shader`]. `defines` are ignored (with a warning) by sokol-shdc programs
+    pub fn build(&self, defines: &[&str]) -> Shader {
+        let mut pip_desc = (self.pip_desc)();
+        match self.source {
+            ShaderSource::Files => gen(self.name, defines, self.mut_shd_desc, &mut pip_desc),
+            ShaderSource::Shdc(file) => {
+                if !defines.is_empty() {
+                    log::warn!("shader `{}` has no variants: {:?}", self.name, defines);
+                }
+                gen_shdc(file, self.name, self.mut_shd_desc, &mut pip_desc)
+            }
+        }
+    }
+
+    /// Checks that the descriptors agree with the GLSL sources (see [`reflect::validate`])
+    pub fn validate(&self, defines: &[&str]) -> Result<(), Vec<String>> {
+        let pip_desc = (self.pip_desc)();
+
+        let res = match self.source {
+            ShaderSource::Files => {
+                let [vs, fs] = self::load_shd(self.name, defines)
+                    .map_err(|err| vec![err])?
+                    .src;
+                let mut shd_desc = unsafe { rokol::gfx::shader_desc(&vs, &fs) };
+                (self.mut_shd_desc)(&mut shd_desc);
+                reflect::validate(&vs, &fs, &shd_desc, &pip_desc.layout)
+            }
+            ShaderSource::Shdc(file) => {
+                let (prog, _) = self::load_shdc(file, self.name).map_err(|err| vec![err])?;
+                let mut shd_desc = prog.shader_desc();
+                (self.mut_shd_desc)(&mut shd_desc);
+                reflect::validate(&prog.vs.src, &prog.fs.src, &shd_desc, &pip_desc.layout)
+            }
         };
-        block.size += std::mem::size_of::<$size_ty>() as u64;
 
-        block
-    }};
+        res.map_err(|errs| {
+            errs.into_iter()
+                .map(|err| format!("{}: {}", self.name, err))
+                .collect()
+        })
+    }
+}
+
+/// Built-in shaders
+pub static BUILTINS: &[ShaderSpec] = &[TRIANGLE, TEXTURE, CUBE, POSTFX, SKYBOX, TONEMAP, BLUR, LIT];
+
+/// Validates all the built-in shaders without `#define`s
+pub fn validate_builtins() -> Result<(), Vec<String>> {
+    let errs: Vec<String> = BUILTINS
+        .iter()
+        .filter_map(|spec| spec.validate(&[]).err())
+        .flatten()
+        .collect();
+
+    if errs.is_empty() {
+        Ok(())
+    } else {
+        Err(errs)
+    }
 }
 
-/// (position, color) vertex
-#[derive(Debug, Clone)]
-#[repr(C)]
-pub struct TriangleVertex {
-    /// X, Y, Z
-    pos: [f32; 3],
-    /// R, G, B, A
-    color: [f32; 4],
+/// Sets image type
+macro_rules! img_type {
+    ($name:expr,$ty:expr) => {
+        rg::ShaderImageDesc {
+            name: concat!($name, "\0").as_ptr() as *const _,
+            image_type: $ty as u32,
+            ..Default::default()
+        }
+    };
 }
 
-impl TriangleVertex {
-    pub fn layout_desc() -> rg::LayoutDesc {
-        let mut desc = rg::LayoutDesc::default();
-        desc.attrs[0].format = rg::VertexFormat::Float3 as u32;
-        desc.attrs[1].format = rg::VertexFormat::Float4 as u32;
-        desc
+crate::vertex! {
+    /// (position, color) vertex
+    pub struct TriangleVertex {
+        /// X, Y, Z
+        pos: [f32; 3],
+        /// R, G, B, A
+        color: [f32; 4],
     }
 }
 
@@ -119,37 +385,30 @@ where
 }
 
 pub fn triangle() -> Shader {
-    gen(
-        &def_shd!("triangle"),
-        |_shd| {},
-        &mut rg::PipelineDesc {
-            index_type: rg::IndexType::UInt16 as u32,
-            layout: TriangleVertex::layout_desc(),
-            // cull_mode: rg::CullMode::None as u32,
-            ..Default::default()
-        },
-    )
+    TRIANGLE.build(&[])
 }
 
-/// (position, color) vertex
-#[derive(Debug, Clone)]
-#[repr(C)]
-pub struct TextureVertex {
-    // /// X, Y, Z
-    pub pos: [f32; 3],
-    // /// R, G, B, A
-    pub color: [u8; 4],
-    // /// u, v
-    pub uv: [f32; 2],
-}
+pub const TRIANGLE: ShaderSpec = ShaderSpec {
+    name: "triangle",
+    source: ShaderSource::Files,
+    mut_shd_desc: |_shd| {},
+    pip_desc: || rg::PipelineDesc {
+        index_type: rg::IndexType::UInt16 as u32,
+        layout: TriangleVertex::layout_desc(),
+        // cull_mode: rg::CullMode::None as u32,
+        ..Default::default()
+    },
+};
 
-impl TextureVertex {
-    pub fn layout_desc() -> rg::LayoutDesc {
-        let mut desc = rg::LayoutDesc::default();
-        desc.attrs[0].format = rg::VertexFormat::Float3 as u32;
-        desc.attrs[1].format = rg::VertexFormat::UByte4N as u32;
-        desc.attrs[2].format = rg::VertexFormat::Float2 as u32;
-        desc
+crate::vertex! {
+    /// (position, color, uv) vertex
+    pub struct TextureVertex {
+        /// X, Y, Z
+        pub pos: [f32; 3],
+        /// R, G, B, A
+        pub color: [u8; 4],
+        /// u, v
+        pub uv: [f32; 2],
     }
 }
 
@@ -168,6 +427,8 @@ where
     }
 }
 
+/// Straight alpha blending (commented out in the [`TEXTURE`] pipeline)
+#[allow(dead_code)]
 const ALPHA_BLEND: rg::BlendState = rg::BlendState {
     enabled: true,
     src_factor_rgb: rg::BlendFactor::SrcAlpha as u32,
@@ -179,33 +440,37 @@ const ALPHA_BLEND: rg::BlendState = rg::BlendState {
 };
 
 pub fn texture() -> Shader {
-    gen(
-        &def_shd!("texture"),
-        |shd| {
-            shd.fs.images[0] = img_type!("tex", rg::ImageType::Dim2);
-        },
-        &mut {
-            let mut pip = rg::PipelineDesc {
-                index_type: rg::IndexType::UInt16 as u32,
-                layout: TextureVertex::layout_desc(),
-                cull_mode: rg::CullMode::None as u32,
-                ..Default::default()
-            };
-            // pip.colors[0].blend = ALPHA_BLEND;
-            pip
-        },
-    )
+    TEXTURE.build(&[])
 }
 
-#[derive(Debug, Clone)]
-#[repr(C)]
-pub struct CubeVertex {
-    /// X, Y, Z
-    pos: [f32; 3],
-    /// R, G, B, A
-    color: [u8; 4],
-    /// u, v (texture coordinates)
-    uv: [f32; 2],
+pub const TEXTURE: ShaderSpec = ShaderSpec {
+    name: "texture",
+    source: ShaderSource::Files,
+    mut_shd_desc: |shd| {
+        shd.fs.images[0] = img_type!("tex", rg::ImageType::Dim2);
+    },
+    pip_desc: || {
+        let pip = rg::PipelineDesc {
+            index_type: rg::IndexType::UInt16 as u32,
+            layout: TextureVertex::layout_desc(),
+            cull_mode: rg::CullMode::None as u32,
+            ..Default::default()
+        };
+        // pip.colors[0].blend = ALPHA_BLEND;
+        pip
+    },
+};
+
+crate::vertex! {
+    /// (position, color, uv) vertex
+    pub struct CubeVertex {
+        /// X, Y, Z
+        pos: [f32; 3],
+        /// R, G, B, A
+        color: [u8; 4],
+        /// u, v (texture coordinates)
+        uv: [f32; 2],
+    }
 }
 
 impl<Pos, Color, Uv> From<(Pos, Color, Uv)> for CubeVertex
@@ -223,22 +488,370 @@ where
     }
 }
 
+crate::uniform_block! {
+    /// Uniforms of `cube.glsl`
+    pub struct CubeUniforms {
+        mvp: glam::Mat4,
+    }
+}
+
 pub fn cube() -> Shader {
-    gen(
-        &def_shd!("cube"),
-        |shd| {
-            shd.fs.images[0] = img_type!("tex", rg::ImageType::Dim2);
-            shd.vs.uniform_blocks[0] = ub!("mvp", rg::UniformType::Mat4, glam::Mat4);
-        },
-        &mut {
-            let mut pip = rg::PipelineDesc {
-                index_type: rg::IndexType::UInt16 as u32,
-                layout: TextureVertex::layout_desc(),
-                cull_mode: rg::CullMode::Back as u32,
+    CUBE.build(&[])
+}
+
+pub const CUBE: ShaderSpec = ShaderSpec {
+    name: "cube",
+    source: ShaderSource::Shdc("cube.glsl"),
+    // uniform blocks and images are set from the source
+    mut_shd_desc: |_shd| {},
+    pip_desc: || rg::PipelineDesc {
+        index_type: rg::IndexType::UInt16 as u32,
+        layout: CubeVertex::layout_desc(),
+        cull_mode: rg::CullMode::Back as u32,
+        ..Default::default()
+    },
+};
+
+crate::vertex! {
+    /// (position, uv) vertex of full-screen triangles
+    pub struct PostVertex {
+        /// X, Y in normalized device coordinates
+        pub pos: [f32; 2],
+        /// u, v
+        pub uv: [f32; 2],
+    }
+}
+
+impl<T, U> From<(T, U)> for PostVertex
+where
+    T: Into<[f32; 2]>,
+    U: Into<[f32; 2]>,
+{
+    fn from(data: (T, U)) -> Self {
+        Self {
+            pos: data.0.into(),
+            uv: data.1.into(),
+        }
+    }
+}
+
+crate::uniform_block! {
+    /// Uniforms of `postfx.fs`
+    pub struct PostFxUniforms {
+        /// 3x3 kernel in the upper-left (there's no `mat3` uniform)
+        kernel: glam::Mat4,
+        /// Size of a texel in UV coordinates
+        texel: [f32; 2],
+    }
+}
+
+/// Full-screen effect. Variants: `INVERT`, `GRAYSCALE`, `KERNEL` or copy (no define)
+pub const POSTFX: ShaderSpec = ShaderSpec {
+    name: "postfx",
+    source: ShaderSource::Files,
+    mut_shd_desc: |shd| {
+        shd.fs.images[0] = img_type!("tex", rg::ImageType::Dim2);
+        shd.fs.uniform_blocks[0] = gfx::uniform_block_desc::<PostFxUniforms>();
+    },
+    pip_desc: || rg::PipelineDesc {
+        index_type: rg::IndexType::UInt16 as u32,
+        layout: PostVertex::layout_desc(),
+        cull_mode: rg::CullMode::None as u32,
+        ..Default::default()
+    },
+};
+
+crate::vertex! {
+    /// Position-only vertex of the skybox cube
+    pub struct SkyboxVertex {
+        /// X, Y, Z (also the direction to sample the cubemap)
+        pub pos: [f32; 3],
+    }
+}
+
+impl<T> From<T> for SkyboxVertex
+where
+    T: Into<[f32; 3]>,
+{
+    fn from(pos: T) -> Self {
+        Self { pos: pos.into() }
+    }
+}
+
+crate::uniform_block! {
+    /// Uniforms of `skybox.vs`
+    pub struct SkyboxUniforms {
+        /// Projection * view matrix without translation
+        view_proj: glam::Mat4,
+    }
+}
+
+pub fn skybox() -> Shader {
+    SKYBOX.build(&[])
+}
+
+pub const SKYBOX: ShaderSpec = ShaderSpec {
+    name: "skybox",
+    source: ShaderSource::Files,
+    mut_shd_desc: |shd| {
+        shd.fs.images[0] = img_type!("skybox", rg::ImageType::Cube);
+        shd.vs.uniform_blocks[0] = gfx::uniform_block_desc::<SkyboxUniforms>();
+    },
+    pip_desc: || {
+        let mut pip = rg::PipelineDesc {
+            index_type: rg::IndexType::UInt16 as u32,
+            layout: SkyboxVertex::layout_desc(),
+            cull_mode: rg::CullMode::None as u32,
+            ..Default::default()
+        };
+        // drawn after the scene at the farthest depth
+        pip.depth.compare = rg::CompareFunc::LessEqual as u32;
+        pip.depth.write_enabled = false;
+        pip
+    },
+};
+
+crate::uniform_block! {
+    /// Uniforms of `tonemap.fs`
+    pub struct ToneMapUniforms {
+        exposure: f32,
+        gamma: f32,
+        /// Scale of the bloom added to the scene (`BLOOM` variant)
+        bloom_strength: f32,
+    }
+}
+
+/// Resolves HDR images. Variants: `TONEMAP_REINHARD`, `TONEMAP_EXPOSURE`, `TONEMAP_ACES` or
+/// clamp (no define), and `BLOOM`
+pub const TONEMAP: ShaderSpec = ShaderSpec {
+    name: "tonemap",
+    source: ShaderSource::Files,
+    mut_shd_desc: |shd| {
+        shd.fs.images[0] = img_type!("hdr", rg::ImageType::Dim2);
+        shd.fs.images[1] = img_type!("bloom", rg::ImageType::Dim2);
+        shd.fs.uniform_blocks[0] = gfx::uniform_block_desc::<ToneMapUniforms>();
+    },
+    pip_desc: || rg::PipelineDesc {
+        index_type: rg::IndexType::UInt16 as u32,
+        layout: PostVertex::layout_desc(),
+        cull_mode: rg::CullMode::None as u32,
+        ..Default::default()
+    },
+};
+
+crate::uniform_block! {
+    /// Uniforms of `blur.fs`
+    pub struct BlurUniforms {
+        /// Gaussian weights from the center texel (see [`crate::bloom::pack_weights`])
+        weights: [[f32; 4]; 4],
+        /// Texel step in the blur direction in UV coordinates
+        dir: [f32; 2],
+        /// Number of texels sampled on each side
+        radius: f32,
+    }
+}
+
+/// One direction of a separable Gaussian blur into [`crate::hdr::HDR_FORMAT`] targets without
+/// depth
+pub const BLUR: ShaderSpec = ShaderSpec {
+    name: "blur",
+    source: ShaderSource::Files,
+    mut_shd_desc: |shd| {
+        shd.fs.images[0] = img_type!("tex", rg::ImageType::Dim2);
+        shd.fs.uniform_blocks[0] = gfx::uniform_block_desc::<BlurUniforms>();
+    },
+    pip_desc: || {
+        let mut pip = rg::PipelineDesc {
+            index_type: rg::IndexType::UInt16 as u32,
+            layout: PostVertex::layout_desc(),
+            cull_mode: rg::CullMode::None as u32,
+            ..Default::default()
+        };
+        pip.colors[0].pixel_format = crate::hdr::HDR_FORMAT as u32;
+        pip.depth.pixel_format = rg::PixelFormat::None as u32;
+        pip
+    },
+};
+
+crate::vertex! {
+    /// (position, normal, uv) vertex of lit meshes
+    pub struct LitVertex {
+        /// X, Y, Z
+        pos: [f32; 3],
+        /// Unit vector in model space
+        normal: [f32; 3],
+        /// u, v
+        uv: [f32; 2],
+    }
+}
+
+impl<Pos, Normal, Uv> From<(Pos, Normal, Uv)> for LitVertex
+where
+    Pos: Into<[f32; 3]>,
+    Normal: Into<[f32; 3]>,
+    Uv: Into<[f32; 2]>,
+{
+    fn from(data: (Pos, Normal, Uv)) -> Self {
+        Self {
+            pos: data.0.into(),
+            normal: data.1.into(),
+            uv: data.2.into(),
+        }
+    }
+}
+
+crate::uniform_block! {
+    /// Uniforms of `lit.vs`
+    pub struct LitVsUniforms {
+        model: glam::Mat4,
+        view_proj: glam::Mat4,
+        /// Inverse transpose of `model`
+        normal_mat: glam::Mat4,
+    }
+}
+
+impl LitVsUniforms {
+    pub fn new(model: glam::Mat4, view_proj: glam::Mat4) -> Self {
+        Self {
+            model,
+            view_proj,
+            normal_mat: model.inverse().transpose(),
+        }
+    }
+}
+
+crate::uniform_block! {
+    /// Uniforms of `lighting.fs.glsl`. Create it with [`crate::light::Lights::uniforms`]
+    pub struct LightUniforms {
+        /// xyz: position, w: kind
+        light_pos: [[f32; 4]; 8],
+        /// xyz: direction, w: cosine of the inner cutoff
+        light_dir: [[f32; 4]; 8],
+        light_ambient: [[f32; 4]; 8],
+        light_diffuse: [[f32; 4]; 8],
+        light_specular: [[f32; 4]; 8],
+        /// x: constant, y: linear, z: quadratic, w: cosine of the outer cutoff
+        light_params: [[f32; 4]; 8],
+        view_pos: [f32; 3],
+        light_count: f32,
+        /// 0: Phong, 1: Blinn-Phong
+        blinn: f32,
+    }
+}
+
+crate::uniform_block! {
+    /// Material uniforms of `lit.fs`. Create it with [`crate::material::Material::uniforms`]
+    pub struct MaterialUniforms {
+        /// Multiplied to the diffuse map
+        tint: [f32; 4],
+        /// Multiplied to the emission map (`EMISSION_MAP` variant)
+        emission: [f32; 3],
+        /// Specular exponent
+        shininess: f32,
+    }
+}
+
+/// Phong / Blinn-Phong shading of [`LitVertex`] drawn into [`crate::hdr::Hdr::scene_pass`].
+/// Variants: `NORMAL_MAP` and `EMISSION_MAP`
+///
+/// Uniform blocks: [`LitVsUniforms`], [`LightUniforms`] (fs 0) and [`MaterialUniforms`] (fs 1).
+/// Images are bound by [`crate::material::Material`].
+pub fn lit() -> Shader {
+    LIT.build(&[])
+}
+
+pub const LIT: ShaderSpec = ShaderSpec {
+    name: "lit",
+    source: ShaderSource::Files,
+    mut_shd_desc: |shd| {
+        shd.vs.uniform_blocks[0] = gfx::uniform_block_desc::<LitVsUniforms>();
+        shd.fs.images[0] = img_type!("diffuse_map", rg::ImageType::Dim2);
+        shd.fs.images[1] = img_type!("specular_map", rg::ImageType::Dim2);
+        shd.fs.images[2] = img_type!("normal_map", rg::ImageType::Dim2);
+        shd.fs.images[3] = img_type!("emission_map", rg::ImageType::Dim2);
+        shd.fs.uniform_blocks[0] = gfx::uniform_block_desc::<LightUniforms>();
+        shd.fs.uniform_blocks[1] = gfx::uniform_block_desc::<MaterialUniforms>();
+    },
+    pip_desc: || {
+        let mut pip = rg::PipelineDesc {
+            index_type: rg::IndexType::UInt16 as u32,
+            layout: LitVertex::layout_desc(),
+            cull_mode: rg::CullMode::Back as u32,
+            ..Default::default()
+        };
+        pip.depth.compare = rg::CompareFunc::LessEqual as u32;
+        pip.depth.write_enabled = true;
+        crate::hdr::scene_pip(&mut pip);
+        pip
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::gfx::UniformBlock;
+
+    #[test]
+    fn glsl_files_are_embedded() {
+        for entry in std::fs::read_dir(self::glsl_dir()).unwrap() {
+            let name = entry.unwrap().file_name().into_string().unwrap();
+            assert!(
+                GLSL_FILES.iter().any(|(file, _)| *file == name),
+                "{} is not in GLSL_FILES",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn builtins_are_valid() {
+        self::validate_builtins().unwrap();
+    }
+
+    #[test]
+    fn cube_block_matches_uniforms() {
+        let (prog, _) = self::load_shdc("cube.glsl", "cube").unwrap();
+        assert_eq!(prog.vs.blocks.len(), 1);
+        assert_eq!(prog.vs.blocks[0].size(), CubeUniforms::size());
+        assert_eq!(prog.fs.images.len(), 1);
+    }
+
+    #[test]
+    fn missing_uniform_block_is_reported() {
+        let spec = ShaderSpec {
+            mut_shd_desc: |shd| {
+                shd.fs.images[0] = img_type!("tex", rg::ImageType::Dim2);
+            },
+            ..POSTFX
+        };
+
+        let errs = spec.validate(&[]).unwrap_err();
+        assert!(!errs.is_empty());
+        assert!(errs.iter().all(|e| e.starts_with("postfx: ")), "{:?}", errs);
+        assert!(errs.iter().any(|e| e.contains("kernel")), "{:?}", errs);
+    }
+
+    #[test]
+    fn mismatched_vertex_layout_is_reported() {
+        let spec = ShaderSpec {
+            pip_desc: || rg::PipelineDesc {
+                layout: PostVertex::layout_desc(),
                 ..Default::default()
-            };
-            // pip.colors[0].blend = ALPHA_BLEND;
-            pip
-        },
-    )
+            },
+            ..CUBE
+        };
+
+        let errs = spec.validate(&[]).unwrap_err();
+        assert!(errs.iter().any(|e| e.contains("vs: input")), "{:?}", errs);
+    }
+
+    #[test]
+    fn missing_source_is_reported() {
+        let spec = ShaderSpec {
+            name: "no_such_shader",
+            ..TRIANGLE
+        };
+        assert_eq!(spec.validate(&[]).unwrap_err().len(), 1);
+    }
 }
@@ -0,0 +1,304 @@
+/*!
+GLSL preprocessor: `#include` and `#define` injection
+
+* `#include "file.glsl"` is replaced with the file content. Each file is included at most once per
+  shader, and cyclic includes are errors.
+* Defines are inserted right after the `#version` line.
+* `#line` directives are emitted so that GLSL compile errors refer to `<file index>(<line>)`, where
+  the file index is the position in [`Output::files`].
+*/
+
+use std::fmt;
+
+/// Error with the original location
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessError {
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.msg)
+    }
+}
+
+impl std::error::Error for PreprocessError {}
+
+pub type Result<T> = std::result::Result<T, PreprocessError>;
+
+/// Preprocessed source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    /// Preprocessed source (not null-terminated)
+    pub src: String,
+    /// Files in the order of first inclusion. The first one is the root file
+    pub files: Vec<String>,
+    /// (file index, 1-based line number) for each line of `src`. `None` for generated lines
+    lines: Vec<Option<(usize, usize)>>,
+}
+
+impl Output {
+    /// Maps a 1-based line number in [`Output::src`] to the original file and line
+    pub fn map_line(&self, line: usize) -> Option<(&str, usize)> {
+        let (file, line) = (*self.lines.get(line.checked_sub(1)?)?)?;
+        Some((&self.files[file], line))
+    }
+
+    /// File indices of the `#line` directives, e.g. `0: lit.fs, 1: lighting.fs.glsl`
+    pub fn file_indices(&self) -> String {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| format!("{}: {}", i, file))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Replaces `<file index>(` in GLSL compile logs with `<file name>(`
+    pub fn map_log(&self, log: &str) -> String {
+        let mut log = log.to_string();
+        // replace longer indices first so that `1(` doesn't match in `11(`
+        for (i, file) in self.files.iter().enumerate().rev() {
+            log = log.replace(&format!("{}(", i), &format!("{}(", file));
+        }
+        log
+    }
+}
+
+/// Preprocesses `root` file
+///
+/// * `defines`: `NAME` or `NAME=VALUE`
+/// * `load`: returns the content of a file in the shader directory
+pub fn preprocess(
+    root: &str,
+    defines: &[&str],
+    load: &mut dyn FnMut(&str) -> Option<String>,
+) -> Result<Output> {
+    let src = load(root).ok_or_else(|| PreprocessError {
+        file: root.to_string(),
+        line: 0,
+        msg: "file not found".to_string(),
+    })?;
+
+    let mut pp = Preprocessor {
+        out: Output {
+            src: String::with_capacity(src.len()),
+            files: vec![root.to_string()],
+            lines: Vec::new(),
+        },
+        stack: Vec::new(),
+        defines,
+        load,
+    };
+
+    if !src.lines().any(|l| l.trim_start().starts_with("#version")) {
+        pp.push_defines();
+        pp.push_line_directive(0, 1);
+    }
+
+    pp.run(0, &src)?;
+    Ok(pp.out)
+}
+
+struct Preprocessor<'a> {
+    out: Output,
+    /// Indices of the files being included, to detect cycles
+    stack: Vec<usize>,
+    defines: &'a [&'a str],
+    load: &'a mut dyn FnMut(&str) -> Option<String>,
+}
+
+impl<'a> Preprocessor<'a> {
+    fn push_line(&mut self, line: &str, origin: Option<(usize, usize)>) {
+        self.out.src.push_str(line);
+        self.out.src.push('\n');
+        self.out.lines.push(origin);
+    }
+
+    /// `#line` directive so that the next line is reported as `line` of `file`
+    fn push_line_directive(&mut self, file: usize, line: usize) {
+        self.push_line(&format!("#line {} {}", line, file), None);
+    }
+
+    fn push_defines(&mut self) {
+        for def in self.defines {
+            let def = def.replacen('=', " ", 1);
+            self.push_line(&format!("#define {}", def), None);
+        }
+    }
+
+    fn run(&mut self, file: usize, src: &str) -> Result<()> {
+        self.stack.push(file);
+
+        for (i, line) in src.lines().enumerate() {
+            let line_no = i + 1;
+            let trimmed = line.trim_start();
+
+            if trimmed.starts_with("#version") {
+                if file != 0 {
+                    return Err(self.error(file, line_no, "`#version` in included file"));
+                }
+
+                self.push_line(line, Some((file, line_no)));
+                self.push_defines();
+                self.push_line_directive(file, line_no + 1);
+                continue;
+            }
+
+            if let Some(rest) = trimmed.strip_prefix("#include") {
+                let name = self::parse_include(rest)
+                    .ok_or_else(|| self.error(file, line_no, "expected `#include \"file\"`"))?;
+                self.include(file, line_no, name)?;
+                self.push_line_directive(file, line_no + 1);
+                continue;
+            }
+
+            self.push_line(line, Some((file, line_no)));
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn include(&mut self, parent: usize, line_no: usize, name: &str) -> Result<()> {
+        if let Some(ix) = self.out.files.iter().position(|f| f == name) {
+            if self.stack.contains(&ix) {
+                let chain = self
+                    .stack
+                    .iter()
+                    .map(|&i| self.out.files[i].as_str())
+                    .chain(std::iter::once(name))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                return Err(self.error(parent, line_no, &format!("cyclic include: {}", chain)));
+            }
+            // already included
+            return Ok(());
+        }
+
+        let src = (self.load)(name).ok_or_else(|| {
+            self.error(
+                parent,
+                line_no,
+                &format!("include file not found: `{}`", name),
+            )
+        })?;
+
+        let ix = self.out.files.len();
+        self.out.files.push(name.to_string());
+        self.push_line_directive(ix, 1);
+        self.run(ix, &src)
+    }
+
+    fn error(&self, file: usize, line: usize, msg: &str) -> PreprocessError {
+        PreprocessError {
+            file: self.out.files[file].clone(),
+            line,
+            msg: msg.to_string(),
+        }
+    }
+}
+
+/// ` "file.glsl"` -> `file.glsl`
+fn parse_include(rest: &str) -> Option<&str> {
+    let rest = rest.trim();
+    let rest = rest.strip_prefix('"')?;
+    let end = rest.find('"')?;
+    Some(&rest[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(root: &str, defines: &[&str], files: &[(&str, &str)]) -> Result<Output> {
+        self::preprocess(root, defines, &mut |name| {
+            files
+                .iter()
+                .find(|(file, _)| *file == name)
+                .map(|(_, src)| src.to_string())
+        })
+    }
+
+    const FILES: &[(&str, &str)] = &[
+        (
+            "a.fs",
+            "#version 330\n#include \"b.glsl\"\nvoid main() {}\n#include \"b.glsl\"\n",
+        ),
+        ("b.glsl", "#include \"c.glsl\"\nfloat b;\n"),
+        ("c.glsl", "float c;\n"),
+    ];
+
+    #[test]
+    fn include_line_mapping() {
+        let out = run("a.fs", &["X=1"], FILES).unwrap();
+        assert_eq!(out.files, vec!["a.fs", "b.glsl", "c.glsl"]);
+
+        let lines: Vec<_> = out.src.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "#version 330",
+                "#define X 1",
+                "#line 2 0",
+                "#line 1 1",
+                "#line 1 2",
+                "float c;",
+                "#line 2 1",
+                "float b;",
+                "#line 3 0",
+                "void main() {}",
+                "#line 5 0",
+            ]
+        );
+
+        assert_eq!(out.map_line(1), Some(("a.fs", 1)));
+        assert_eq!(out.map_line(2), None);
+        assert_eq!(out.map_line(6), Some(("c.glsl", 1)));
+        assert_eq!(out.map_line(8), Some(("b.glsl", 2)));
+        assert_eq!(out.map_line(10), Some(("a.fs", 3)));
+        assert_eq!(out.map_line(0), None);
+        assert_eq!(out.map_line(100), None);
+    }
+
+    #[test]
+    fn defines_without_version() {
+        let out = run("c.glsl", &["A", "B=2"], FILES).unwrap();
+        let lines: Vec<_> = out.src.lines().collect();
+        assert_eq!(
+            lines,
+            vec!["#define A", "#define B 2", "#line 1 0", "float c;"]
+        );
+        assert_eq!(out.map_line(4), Some(("c.glsl", 1)));
+    }
+
+    #[test]
+    fn log_mapping() {
+        let out = run("a.fs", &[], FILES).unwrap();
+        assert_eq!(
+            out.map_log("1(2) : error C0000: syntax error"),
+            "b.glsl(2) : error C0000: syntax error"
+        );
+        assert_eq!(out.file_indices(), "0: a.fs, 1: b.glsl, 2: c.glsl");
+    }
+
+    #[test]
+    fn cyclic_include() {
+        let files = &[
+            ("x.fs", "#include \"y.glsl\"\n"),
+            ("y.glsl", "#include \"x.fs\"\n"),
+        ];
+        let err = run("x.fs", &[], files).unwrap_err();
+        assert_eq!((err.file.as_str(), err.line), ("y.glsl", 1));
+        assert!(err.msg.contains("x.fs -> y.glsl -> x.fs"), "{}", err.msg);
+    }
+
+    #[test]
+    fn missing_include() {
+        let err = run("x.fs", &[], &[("x.fs", "\n#include \"none.glsl\"\n")]).unwrap_err();
+        assert_eq!((err.file.as_str(), err.line), ("x.fs", 2));
+    }
+}
@@ -0,0 +1,546 @@
+/*!
+GLSL declaration scanner and validation against the Rust descriptors
+
+The scanner only looks at global declarations (`uniform`, `in` and `out`). Run it on preprocessed
+sources (`#include`s resolved).
+
+It doesn't evaluate macros: directive lines are skipped, so declarations on both sides of
+`#ifdef`/`#else` are scanned, and macro names can't be used as array lengths (that's why
+`lighting.fs.glsl` spells out the size of the light arrays instead of using a `MAX_LIGHTS` macro).
+*/
+
+use std::ffi::CStr;
+
+use rokol::gfx as rg;
+
+/// Global variable declared in GLSL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlslVar {
+    pub name: String,
+    /// GLSL type name such as `vec3` or `sampler2D`
+    pub ty: String,
+    /// Array length, or 1 if it's not an array
+    pub count: usize,
+    /// `layout(location=N)`
+    pub location: Option<usize>,
+}
+
+/// Declarations of a shader stage
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reflection {
+    /// Non-opaque uniforms
+    pub uniforms: Vec<GlslVar>,
+    /// `sampler*` uniforms
+    pub samplers: Vec<GlslVar>,
+    pub inputs: Vec<GlslVar>,
+    pub outputs: Vec<GlslVar>,
+}
+
+impl Reflection {
+    /// Scans global declarations in a GLSL source
+    pub fn scan(src: &str) -> Result<Self, String> {
+        let mut refl = Self::default();
+
+        for stmt in self::global_statements(&self::strip_comments(src)) {
+            self::scan_statement(&stmt, &mut refl)?;
+        }
+
+        Ok(refl)
+    }
+}
+
+/// Replaces comments with spaces (keeping newlines)
+fn strip_comments(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                while let Some(&c) = chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in &mut chars {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    prev = c;
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Statements outside of braces, excluding preprocessor directives and function definitions
+fn global_statements(src: &str) -> Vec<String> {
+    let mut stmts = Vec::new();
+    let mut stmt = String::new();
+    let mut depth = 0usize;
+
+    for line in src.lines() {
+        if depth == 0 && line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        for c in line.chars() {
+            match c {
+                '{' => {
+                    if depth == 0 {
+                        // function definition (or interface block, which is not supported)
+                        stmt.clear();
+                    }
+                    depth += 1;
+                }
+                '}' => depth = depth.saturating_sub(1),
+                ';' if depth == 0 => {
+                    let s = stmt.trim();
+                    if !s.is_empty() {
+                        stmts.push(s.to_string());
+                    }
+                    stmt.clear();
+                }
+                // NUL terminator
+                '\0' => {}
+                _ if depth == 0 => stmt.push(c),
+                _ => {}
+            }
+        }
+        stmt.push(' ');
+    }
+
+    stmts
+}
+
+/// Qualifiers that don't affect the validation
+const IGNORED_QUALIFIERS: &[&str] = &[
+    "highp",
+    "mediump",
+    "lowp",
+    "flat",
+    "smooth",
+    "noperspective",
+    "centroid",
+    "const",
+];
+
+fn scan_statement(stmt: &str, refl: &mut Reflection) -> Result<(), String> {
+    let (location, rest) = self::parse_layout(stmt)?;
+
+    let mut words = rest
+        .split_whitespace()
+        .filter(|w| !IGNORED_QUALIFIERS.contains(w));
+
+    let storage = match words.next() {
+        Some(w) => w,
+        None => return Ok(()),
+    };
+
+    let mut list = match storage {
+        "uniform" => None,
+        "in" => Some(&mut refl.inputs),
+        "out" => Some(&mut refl.outputs),
+        // `precision`, global variables, etc.
+        _ => return Ok(()),
+    };
+
+    let ty = words
+        .next()
+        .ok_or_else(|| format!("missing type: `{}`", stmt))?
+        .to_string();
+
+    // `a, b[2]` (spaces around brackets are allowed)
+    let names = words.collect::<Vec<_>>().join("");
+    for decl in names.split(',') {
+        let (name, count) =
+            self::parse_array(decl).ok_or_else(|| format!("bad declaration: `{}`", stmt))?;
+
+        let var = GlslVar {
+            name: name.to_string(),
+            ty: ty.clone(),
+            count,
+            location,
+        };
+
+        match list {
+            Some(ref mut list) => list.push(var),
+            None if ty.starts_with("sampler") => refl.samplers.push(var),
+            None => refl.uniforms.push(var),
+        }
+    }
+
+    Ok(())
+}
+
+/// `layout(location = N) rest` -> `(Some(N), rest)`
+fn parse_layout(stmt: &str) -> Result<(Option<usize>, &str), String> {
+    let stmt = stmt.trim_start();
+    if !stmt.starts_with("layout") {
+        return Ok((None, stmt));
+    }
+
+    let open = stmt
+        .find('(')
+        .ok_or_else(|| format!("bad layout: `{}`", stmt))?;
+    let close = stmt
+        .find(')')
+        .ok_or_else(|| format!("bad layout: `{}`", stmt))?;
+
+    let mut location = None;
+    for item in stmt[open + 1..close].split(',') {
+        let mut kv = item.splitn(2, '=');
+        let key = kv.next().unwrap_or("").trim();
+        if key == "location" {
+            let value = kv.next().unwrap_or("").trim();
+            location = Some(
+                value
+                    .parse()
+                    .map_err(|_| format!("bad location: `{}`", stmt))?,
+            );
+        }
+    }
+
+    Ok((location, &stmt[close + 1..]))
+}
+
+/// `name[N]` -> `(name, N)`, `name` -> `(name, 1)`
+fn parse_array(decl: &str) -> Option<(&str, usize)> {
+    let decl = decl.trim();
+    match decl.find('[') {
+        Some(open) => {
+            let close = decl.find(']')?;
+            let count = decl[open + 1..close].trim().parse().ok()?;
+            Some((&decl[..open], count))
+        }
+        None if !decl.is_empty() => Some((decl, 1)),
+        None => None,
+    }
+}
+
+/// GLSL type name of a uniform type
+fn uniform_type_name(ty: u32) -> Option<&'static str> {
+    Some(match ty {
+        x if x == rg::UniformType::Float as u32 => "float",
+        x if x == rg::UniformType::Float2 as u32 => "vec2",
+        x if x == rg::UniformType::Float3 as u32 => "vec3",
+        x if x == rg::UniformType::Float4 as u32 => "vec4",
+        x if x == rg::UniformType::Mat4 as u32 => "mat4",
+        _ => return None,
+    })
+}
+
+/// GLSL sampler type name of an image type
+fn image_type_name(ty: u32) -> Option<&'static str> {
+    Some(match ty {
+        x if x == rg::ImageType::Dim2 as u32 => "sampler2D",
+        x if x == rg::ImageType::Cube as u32 => "samplerCube",
+        x if x == rg::ImageType::Dim3 as u32 => "sampler3D",
+        x if x == rg::ImageType::Array as u32 => "sampler2DArray",
+        _ => return None,
+    })
+}
+
+/// Number of components of a vertex format
+fn vertex_format_components(format: u32) -> usize {
+    match format {
+        x if x == rg::VertexFormat::Float as u32 => 1,
+        x if x == rg::VertexFormat::Float2 as u32 => 2,
+        x if x == rg::VertexFormat::Float3 as u32 => 3,
+        x if x == rg::VertexFormat::Short2 as u32 => 2,
+        x if x == rg::VertexFormat::Short2N as u32 => 2,
+        x if x == rg::VertexFormat::UShort2N as u32 => 2,
+        _ => 4,
+    }
+}
+
+/// Number of components of a GLSL vertex input type
+fn glsl_components(ty: &str) -> Option<usize> {
+    Some(match ty {
+        "float" | "int" | "uint" => 1,
+        "vec2" | "ivec2" | "uvec2" => 2,
+        "vec3" | "ivec3" | "uvec3" => 3,
+        "vec4" | "ivec4" | "uvec4" => 4,
+        _ => return None,
+    })
+}
+
+unsafe fn c_str(ptr: *const std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
+
+/// Checks that the descriptors agree with the GLSL sources
+///
+/// * Vertex inputs match the vertex layout
+/// * Uniforms and samplers are named in the shader stage descriptors with the right types
+/// * Vertex shader outputs match fragment shader inputs
+pub fn validate(
+    vs: &str,
+    fs: &str,
+    shd: &rg::ShaderDesc,
+    layout: &rg::LayoutDesc,
+) -> Result<(), Vec<String>> {
+    let vs_refl = Reflection::scan(vs).map_err(|e| vec![format!("vs: {}", e)])?;
+    let fs_refl = Reflection::scan(fs).map_err(|e| vec![format!("fs: {}", e)])?;
+
+    let mut errs = Vec::new();
+
+    self::check_vertex_inputs(&vs_refl, layout, &mut errs);
+    self::check_stage("vs", &vs_refl, &shd.vs, &mut errs);
+    self::check_stage("fs", &fs_refl, &shd.fs, &mut errs);
+    self::check_varyings(&vs_refl, &fs_refl, &mut errs);
+
+    if errs.is_empty() {
+        Ok(())
+    } else {
+        Err(errs)
+    }
+}
+
+fn check_vertex_inputs(vs: &Reflection, layout: &rg::LayoutDesc, errs: &mut Vec<String>) {
+    // attributes end with an invalid (zero) format
+    let n_attrs = layout.attrs.iter().take_while(|a| a.format != 0).count();
+
+    for (i, input) in vs.inputs.iter().enumerate() {
+        let loc = match input.location {
+            Some(loc) => loc,
+            None => {
+                errs.push(format!(
+                    "vs: input `{}` has no `layout(location=N)`",
+                    input.name
+                ));
+                continue;
+            }
+        };
+
+        if loc >= n_attrs {
+            errs.push(format!(
+                "vs: input `{}` at location {} has no vertex attribute ({} attributes)",
+                input.name, loc, n_attrs
+            ));
+            continue;
+        }
+
+        let components = self::vertex_format_components(layout.attrs[loc].format);
+        match self::glsl_components(&input.ty) {
+            Some(n) if components <= n => {}
+            Some(n) => errs.push(format!(
+                "vs: input `{}: {}` at location {} has {} components, but the vertex attribute has {}",
+                input.name, input.ty, loc, n, components
+            )),
+            None => errs.push(format!(
+                "vs: unsupported input type `{}` of `{}`",
+                input.ty, input.name
+            )),
+        }
+
+        if vs.inputs[..i].iter().any(|x| x.location == Some(loc)) {
+            errs.push(format!("vs: location {} is used twice", loc));
+        }
+    }
+
+    if vs.inputs.len() < n_attrs {
+        errs.push(format!(
+            "vs: {} vertex attributes, but {} inputs",
+            n_attrs,
+            vs.inputs.len()
+        ));
+    }
+}
+
+fn check_stage(stage: &str, refl: &Reflection, desc: &rg::ShaderStageDesc, errs: &mut Vec<String>) {
+    // uniforms (all in the uniform blocks)
+    let mut desc_uniforms = Vec::new();
+    for block in desc.uniform_blocks.iter().filter(|b| b.size > 0) {
+        // uniforms end with an invalid (zero) type
+        for u in block.uniforms.iter().take_while(|u| u.type_ != 0) {
+            match unsafe { self::c_str(u.name) } {
+                Some(name) => desc_uniforms.push((name, u.type_, u.array_count.max(1) as usize)),
+                None => errs.push(format!("{}: unnamed uniform in the descriptor", stage)),
+            }
+        }
+    }
+
+    for u in &refl.uniforms {
+        match desc_uniforms.iter().find(|(name, _, _)| *name == u.name) {
+            Some((_, ty, count)) => {
+                let desc_ty = self::uniform_type_name(*ty).unwrap_or("<unsupported>");
+                if desc_ty != u.ty || *count != u.count {
+                    errs.push(format!(
+                        "{}: uniform `{}` is `{}[{}]` in GLSL, but `{}[{}]` in the descriptor",
+                        stage, u.name, u.ty, u.count, desc_ty, count
+                    ));
+                }
+            }
+            None => errs.push(format!(
+                "{}: uniform `{}` is missing in the descriptor",
+                stage, u.name
+            )),
+        }
+    }
+
+    for (name, _, _) in &desc_uniforms {
+        if !refl.uniforms.iter().any(|u| u.name == *name) {
+            errs.push(format!("{}: uniform `{}` is not in GLSL", stage, name));
+        }
+    }
+
+    // samplers
+    let mut desc_images = Vec::new();
+    // unused images have the default (zero) type
+    for img in desc.images.iter().filter(|img| img.image_type != 0) {
+        match unsafe { self::c_str(img.name) } {
+            Some(name) => desc_images.push((name, img.image_type)),
+            None => errs.push(format!("{}: unnamed image in the descriptor", stage)),
+        }
+    }
+
+    for s in &refl.samplers {
+        match desc_images.iter().find(|(name, _)| *name == s.name) {
+            Some((_, ty)) => {
+                let desc_ty = self::image_type_name(*ty).unwrap_or("<unsupported>");
+                if desc_ty != s.ty {
+                    errs.push(format!(
+                        "{}: sampler `{}` is `{}` in GLSL, but `{}` in the descriptor",
+                        stage, s.name, s.ty, desc_ty
+                    ));
+                }
+            }
+            None => errs.push(format!(
+                "{}: sampler `{}` is missing in the descriptor",
+                stage, s.name
+            )),
+        }
+    }
+
+    for (name, _) in &desc_images {
+        if !refl.samplers.iter().any(|s| s.name == *name) {
+            errs.push(format!("{}: image `{}` is not in GLSL", stage, name));
+        }
+    }
+}
+
+fn check_varyings(vs: &Reflection, fs: &Reflection, errs: &mut Vec<String>) {
+    for input in &fs.inputs {
+        // matched by location if specified, or else by name
+        let output = vs
+            .outputs
+            .iter()
+            .find(|o| match (input.location, o.location) {
+                (Some(a), Some(b)) => a == b,
+                _ => o.name == input.name,
+            });
+
+        match output {
+            Some(o) if o.ty != input.ty || o.count != input.count => errs.push(format!(
+                "fs input `{}: {}` doesn't match vs output `{}: {}`",
+                input.name, input.ty, o.name, o.ty
+            )),
+            Some(_) => {}
+            None => errs.push(format!(
+                "fs input `{}` is not written by the vertex shader",
+                input.name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str, ty: &str, count: usize, location: Option<usize>) -> GlslVar {
+        GlslVar {
+            name: name.to_string(),
+            ty: ty.to_string(),
+            count,
+            location,
+        }
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        let src = "
+// uniform vec4 line_comment;
+/* uniform vec4 block_comment;
+   uniform vec4 multi_line; */
+uniform /* inline */ float x; // trailing
+";
+        let refl = Reflection::scan(src).unwrap();
+        assert_eq!(refl.uniforms, vec![var("x", "float", 1, None)]);
+    }
+
+    #[test]
+    fn multiple_declarators() {
+        let refl = Reflection::scan("uniform vec4 a, b[2];\nuniform sampler2D s0,s1;").unwrap();
+        assert_eq!(
+            refl.uniforms,
+            vec![var("a", "vec4", 1, None), var("b", "vec4", 2, None)]
+        );
+        assert_eq!(
+            refl.samplers,
+            vec![
+                var("s0", "sampler2D", 1, None),
+                var("s1", "sampler2D", 1, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_locations() {
+        let src = "
+layout(location=0) in vec3 pos;
+layout (location = 1) in highp vec2 uv;
+in vec4 color;
+layout(location=0) out vec4 frag_color;
+";
+        let refl = Reflection::scan(src).unwrap();
+        assert_eq!(
+            refl.inputs,
+            vec![
+                var("pos", "vec3", 1, Some(0)),
+                var("uv", "vec2", 1, Some(1)),
+                var("color", "vec4", 1, None),
+            ]
+        );
+        assert_eq!(refl.outputs, vec![var("frag_color", "vec4", 1, Some(0))]);
+
+        assert!(Reflection::scan("layout(location=x) in vec3 pos;").is_err());
+    }
+
+    #[test]
+    fn both_sides_of_ifdef_are_scanned() {
+        let src = "
+#ifdef NORMAL_MAP
+uniform sampler2D normal_map;
+#else
+uniform vec3 normal;
+#endif
+void main() { vec4 local; }
+";
+        let refl = Reflection::scan(src).unwrap();
+        assert_eq!(refl.samplers, vec![var("normal_map", "sampler2D", 1, None)]);
+        assert_eq!(refl.uniforms, vec![var("normal", "vec3", 1, None)]);
+    }
+
+    #[test]
+    fn macro_array_length_is_error() {
+        assert!(Reflection::scan("uniform vec4 pos[MAX_LIGHTS];").is_err());
+    }
+}
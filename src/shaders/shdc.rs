@@ -0,0 +1,698 @@
+/*!
+[sokol-shdc] annotated GLSL
+
+A single file defines vertex/fragment shaders and programs:
+
+```glsl
+@block common
+uniform sampler2D tex;
+@end
+
+@vs vs
+uniform vs_params {
+    mat4 mvp;
+};
+
+layout(location=0) in vec3 pos;
+layout(location=1) in vec2 uv;
+out vec2 fs_uv;
+
+void main() {
+    gl_Position = mvp * vec4(pos, 1.0);
+    fs_uv = uv;
+}
+@end
+
+@fs fs
+@include_block common
+in vec2 fs_uv;
+out vec4 frag_color;
+
+void main() {
+    frag_color = texture(tex, fs_uv);
+}
+@end
+
+@program quad vs fs
+```
+
+Each stage is translated into GLSL 330: uniform blocks are flattened into named uniforms, and
+`layout(..)` on uniforms and `layout(location=N)` on varyings are removed. Uniform blocks and
+images are collected so that [`ShdcProgram::shader_desc`] fills the descriptors. Varyings are
+matched by name.
+
+`@ctype`, `@module` and `@*_options` are ignored.
+
+[sokol-shdc]: https://github.com/floooh/sokol-tools/blob/master/docs/sokol-shdc.md
+*/
+
+use std::{collections::HashMap, fmt};
+
+use rokol::gfx as rg;
+
+use crate::gfx::{self, UniformMember};
+
+/// Error with a 1-based line number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShdcError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ShdcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl std::error::Error for ShdcError {}
+
+pub type Result<T> = std::result::Result<T, ShdcError>;
+
+fn error<T>(line: usize, msg: impl Into<String>) -> Result<T> {
+    Err(ShdcError {
+        line,
+        msg: msg.into(),
+    })
+}
+
+/// Member of a uniform block
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShdcUniform {
+    /// Null-terminated name
+    pub name: String,
+    pub ty: rg::UniformType,
+    /// Array length, or 1 if it's not an array
+    pub count: usize,
+}
+
+/// Uniform block
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShdcBlock {
+    pub name: String,
+    pub uniforms: Vec<ShdcUniform>,
+}
+
+impl ShdcBlock {
+    /// Size of the uniforms tightly packed (see [`UniformBlock::size`])
+    ///
+    /// [`UniformBlock::size`]: crate::gfx::UniformBlock::size
+    pub fn size(&self) -> usize {
+        let members: Vec<_> = self
+            .uniforms
+            .iter()
+            .map(|u| UniformMember {
+                name: "\0",
+                ty: u.ty,
+                count: u.count,
+            })
+            .collect();
+        gfx::packed_size(&members)
+    }
+
+    /// The descriptor refers to the names in `self`
+    pub fn desc(&self) -> rg::ShaderUniformBlockDesc {
+        let mut block = rg::ShaderUniformBlockDesc::default();
+        assert!(
+            self.uniforms.len() <= block.uniforms.len(),
+            "too many uniforms in `{}`",
+            self.name
+        );
+
+        for (i, u) in self.uniforms.iter().enumerate() {
+            block.uniforms[i] = rg::ShaderUniformDesc {
+                name: u.name.as_ptr() as *const _,
+                type_: u.ty as u32,
+                array_count: u.count as _,
+                ..Default::default()
+            };
+        }
+        block.size = self.size() as _;
+
+        block
+    }
+}
+
+/// Sampler uniform
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShdcImage {
+    /// Null-terminated name
+    pub name: String,
+    pub ty: rg::ImageType,
+}
+
+/// Vertex or fragment shader translated into GLSL 330
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShdcStage {
+    pub name: String,
+    /// Null-terminated GLSL 330 source
+    pub src: String,
+    /// Uniform blocks in declaration order (slot `i` is `blocks[i]`)
+    pub blocks: Vec<ShdcBlock>,
+    /// Images in declaration order (slot `i` is `images[i]`)
+    pub images: Vec<ShdcImage>,
+}
+
+impl ShdcStage {
+    /// Sets uniform blocks and images. The descriptor refers to the names in `self`
+    pub fn apply(&self, desc: &mut rg::ShaderStageDesc) {
+        assert!(
+            self.blocks.len() <= desc.uniform_blocks.len(),
+            "too many uniform blocks in `{}`",
+            self.name
+        );
+        assert!(
+            self.images.len() <= desc.images.len(),
+            "too many images in `{}`",
+            self.name
+        );
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            desc.uniform_blocks[i] = block.desc();
+        }
+
+        for (i, img) in self.images.iter().enumerate() {
+            desc.images[i] = rg::ShaderImageDesc {
+                name: img.name.as_ptr() as *const _,
+                image_type: img.ty as u32,
+                ..Default::default()
+            };
+        }
+    }
+}
+
+/// `@program`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShdcProgram {
+    pub name: String,
+    pub vs: ShdcStage,
+    pub fs: ShdcStage,
+}
+
+impl ShdcProgram {
+    /// Shader descriptor with sources, uniform blocks and images. The descriptor refers to the
+    /// strings in `self`
+    pub fn shader_desc(&self) -> rg::ShaderDesc {
+        let mut desc = unsafe { rg::shader_desc(&self.vs.src, &self.fs.src) };
+        self.vs.apply(&mut desc.vs);
+        self.fs.apply(&mut desc.fs);
+        desc
+    }
+}
+
+/// Programs in a sokol-shdc annotated GLSL file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShdcFile {
+    pub programs: Vec<ShdcProgram>,
+}
+
+impl ShdcFile {
+    pub fn parse(src: &str) -> Result<Self> {
+        self::parse(src)
+    }
+
+    pub fn program(&self, name: &str) -> Option<&ShdcProgram> {
+        self.programs.iter().find(|p| p.name == name)
+    }
+}
+
+/// Source line with the 1-based line number
+type Lines = Vec<(usize, String)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Block,
+    Vs,
+    Fs,
+}
+
+/// Parses a sokol-shdc annotated GLSL file
+pub fn parse(src: &str) -> Result<ShdcFile> {
+    // name -> lines
+    let mut blocks = HashMap::<String, Lines>::new();
+    let mut vss = HashMap::<String, Lines>::new();
+    let mut fss = HashMap::<String, Lines>::new();
+    // (line, program, vs, fs)
+    let mut programs = Vec::<(usize, String, String, String)>::new();
+
+    let mut current: Option<(Section, String, Lines)> = None;
+
+    for (i, line) in src.lines().enumerate() {
+        let line_no = i + 1;
+        let trimmed = line.trim();
+
+        if !trimmed.starts_with('@') {
+            match current {
+                Some((_, _, ref mut lines)) => lines.push((line_no, line.to_string())),
+                None if trimmed.is_empty() || trimmed.starts_with("//") => {}
+                None => return self::error(line_no, "code outside of `@vs`, `@fs` or `@block`"),
+            }
+            continue;
+        }
+
+        let mut words = trimmed.split_whitespace();
+        let tag = words.next().unwrap();
+        let args: Vec<&str> = words.collect();
+
+        match tag {
+            "@vs" | "@fs" | "@block" => {
+                if current.is_some() {
+                    return self::error(line_no, format!("`{}` before `@end`", tag));
+                }
+                if args.len() != 1 {
+                    return self::error(line_no, format!("expected `{} <name>`", tag));
+                }
+                let section = match tag {
+                    "@vs" => Section::Vs,
+                    "@fs" => Section::Fs,
+                    _ => Section::Block,
+                };
+                current = Some((section, args[0].to_string(), Vec::new()));
+            }
+            "@end" => {
+                let (section, name, lines) = match current.take() {
+                    Some(x) => x,
+                    None => return self::error(line_no, "`@end` without a section"),
+                };
+                let map = match section {
+                    Section::Block => &mut blocks,
+                    Section::Vs => &mut vss,
+                    Section::Fs => &mut fss,
+                };
+                if map.insert(name.clone(), lines).is_some() {
+                    return self::error(line_no, format!("duplicate section `{}`", name));
+                }
+            }
+            "@include_block" => {
+                let lines = match current {
+                    Some((_, _, ref mut lines)) => lines,
+                    None => return self::error(line_no, "`@include_block` outside of a section"),
+                };
+                if args.len() != 1 {
+                    return self::error(line_no, "expected `@include_block <name>`");
+                }
+                let block = blocks.get(args[0]).ok_or_else(|| ShdcError {
+                    line: line_no,
+                    msg: format!("undefined block `{}`", args[0]),
+                })?;
+                lines.extend(block.iter().cloned());
+            }
+            "@program" => {
+                if args.len() != 3 {
+                    return self::error(line_no, "expected `@program <name> <vs> <fs>`");
+                }
+                programs.push((
+                    line_no,
+                    args[0].to_string(),
+                    args[1].to_string(),
+                    args[2].to_string(),
+                ));
+            }
+            "@ctype" | "@module" | "@glsl_options" | "@hlsl_options" | "@msl_options" => {}
+            _ => return self::error(line_no, format!("unknown tag `{}`", tag)),
+        }
+    }
+
+    if current.is_some() {
+        return self::error(src.lines().count(), "missing `@end`");
+    }
+
+    let mut out = Vec::with_capacity(programs.len());
+    for (line_no, name, vs, fs) in programs {
+        let vs_lines = vss.get(&vs).ok_or_else(|| ShdcError {
+            line: line_no,
+            msg: format!("undefined vertex shader `{}`", vs),
+        })?;
+        let fs_lines = fss.get(&fs).ok_or_else(|| ShdcError {
+            line: line_no,
+            msg: format!("undefined fragment shader `{}`", fs),
+        })?;
+
+        out.push(ShdcProgram {
+            name,
+            vs: self::translate(&vs, Section::Vs, vs_lines)?,
+            fs: self::translate(&fs, Section::Fs, fs_lines)?,
+        });
+    }
+
+    Ok(ShdcFile { programs: out })
+}
+
+/// Translates a stage into GLSL 330
+fn translate(name: &str, section: Section, lines: &[(usize, String)]) -> Result<ShdcStage> {
+    let mut out = GlslWriter::new();
+    let mut blocks = Vec::new();
+    let mut images = Vec::new();
+
+    // uniform block being read
+    let mut block: Option<ShdcBlock> = None;
+
+    for (line_no, line) in lines {
+        let line_no = *line_no;
+        let stripped = self::strip_layout(line, section);
+        let trimmed = stripped.trim();
+
+        if let Some(ref mut b) = block {
+            let (members, end) = match trimmed.find('}') {
+                Some(pos) => (&trimmed[..pos], true),
+                None => (trimmed, false),
+            };
+
+            for decl in members.split(';').map(str::trim).filter(|d| !d.is_empty()) {
+                let uniform =
+                    self::parse_member(decl).map_err(|msg| ShdcError { line: line_no, msg })?;
+                out.push(
+                    line_no,
+                    &format!(
+                        "uniform {};",
+                        decl.split_whitespace().collect::<Vec<_>>().join(" ")
+                    ),
+                );
+                b.uniforms.push(uniform);
+            }
+
+            if end {
+                blocks.push(block.take().unwrap());
+            }
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("uniform ") {
+            let rest = rest.trim();
+
+            // uniform block
+            if let Some(open) = rest.find('{') {
+                let block_name = rest[..open].trim();
+                if block_name.is_empty() || block_name.contains(char::is_whitespace) {
+                    return self::error(line_no, format!("bad uniform block: `{}`", trimmed));
+                }
+                block = Some(ShdcBlock {
+                    name: block_name.to_string(),
+                    uniforms: Vec::new(),
+                });
+                // members on the same line are not supported
+                if !rest[open + 1..].trim().is_empty() {
+                    return self::error(line_no, "put uniform block members on separate lines");
+                }
+                continue;
+            }
+
+            if !rest.contains(char::is_whitespace) {
+                // `uniform name` and `{` on the next line
+                return self::error(line_no, "put `{` on the same line as the block name");
+            }
+
+            // sampler
+            let mut words = rest.trim_end_matches(';').split_whitespace();
+            let ty = words.next().unwrap();
+            if ty.starts_with("sampler") {
+                let ty = self::image_type(ty).ok_or_else(|| ShdcError {
+                    line: line_no,
+                    msg: format!("unsupported sampler type `{}`", ty),
+                })?;
+                let img_name = words.next().unwrap_or("");
+                images.push(ShdcImage {
+                    name: format!("{}\0", img_name),
+                    ty,
+                });
+                out.push(line_no, &stripped);
+                continue;
+            }
+
+            return self::error(
+                line_no,
+                "uniforms outside of uniform blocks are not supported",
+            );
+        }
+
+        out.push(line_no, &stripped);
+    }
+
+    if let Some(b) = block {
+        return self::error(
+            lines.last().map(|(l, _)| *l).unwrap_or(0),
+            format!("unterminated uniform block `{}`", b.name),
+        );
+    }
+
+    Ok(ShdcStage {
+        name: name.to_string(),
+        src: out.finish(),
+        blocks,
+        images,
+    })
+}
+
+/// Removes `layout(..)` from uniforms, and `layout(location=N)` from varyings (vertex shader
+/// outputs and fragment shader inputs)
+fn strip_layout(line: &str, section: Section) -> String {
+    let trimmed = line.trim_start();
+    if !trimmed.starts_with("layout") {
+        return line.to_string();
+    }
+
+    let (open, close) = match (trimmed.find('('), trimmed.find(')')) {
+        (Some(open), Some(close)) if open < close => (open, close),
+        _ => return line.to_string(),
+    };
+
+    let rest = trimmed[close + 1..].trim_start();
+    if rest.starts_with("uniform ") {
+        // `binding` and `std140`
+        let indent = &line[..line.len() - trimmed.len()];
+        return format!("{}{}", indent, rest);
+    }
+
+    let is_varying = match section {
+        Section::Vs => rest.starts_with("out "),
+        Section::Fs => rest.starts_with("in "),
+        Section::Block => false,
+    };
+
+    let items: Vec<&str> = trimmed[open + 1..close]
+        .split(',')
+        .map(str::trim)
+        .filter(|item| {
+            let key = item.split('=').next().unwrap_or("").trim();
+            !(key == "binding" || (is_varying && key == "location"))
+        })
+        .collect();
+
+    let indent = &line[..line.len() - trimmed.len()];
+    if items.is_empty() {
+        format!("{}{}", indent, rest)
+    } else {
+        format!("{}layout({}) {}", indent, items.join(", "), rest)
+    }
+}
+
+/// `mat4 mvp` -> uniform
+fn parse_member(decl: &str) -> std::result::Result<ShdcUniform, String> {
+    let mut words = decl.split_whitespace();
+    let ty = words
+        .next()
+        .ok_or_else(|| format!("bad uniform: `{}`", decl))?;
+    let name = words.collect::<Vec<_>>().join("");
+
+    let ty = match ty {
+        "float" => rg::UniformType::Float,
+        "vec2" => rg::UniformType::Float2,
+        "vec3" => rg::UniformType::Float3,
+        "vec4" => rg::UniformType::Float4,
+        "mat4" => rg::UniformType::Mat4,
+        _ => return Err(format!("unsupported uniform type `{}`", ty)),
+    };
+
+    let (name, count) = match name.find('[') {
+        Some(open) => {
+            let close = name
+                .find(']')
+                .ok_or_else(|| format!("bad uniform: `{}`", decl))?;
+            let count = name[open + 1..close]
+                .parse()
+                .map_err(|_| format!("bad array length: `{}`", decl))?;
+            (&name[..open], count)
+        }
+        None => (name.as_str(), 1),
+    };
+
+    if name.is_empty() {
+        return Err(format!("bad uniform: `{}`", decl));
+    }
+
+    Ok(ShdcUniform {
+        name: format!("{}\0", name),
+        ty,
+        count,
+    })
+}
+
+fn image_type(ty: &str) -> Option<rg::ImageType> {
+    Some(match ty {
+        "sampler2D" => rg::ImageType::Dim2,
+        "samplerCube" => rg::ImageType::Cube,
+        "sampler3D" => rg::ImageType::Dim3,
+        "sampler2DArray" => rg::ImageType::Array,
+        _ => return None,
+    })
+}
+
+/// Writes GLSL 330 lines with `#line` directives pointing to the annotated file
+struct GlslWriter {
+    src: String,
+    /// Line number GLSL assigns to the next line
+    next_line: usize,
+}
+
+impl GlslWriter {
+    fn new() -> Self {
+        Self {
+            src: "#version 330\n".to_string(),
+            next_line: 2,
+        }
+    }
+
+    fn push(&mut self, line_no: usize, line: &str) {
+        if line_no != self.next_line {
+            self.src.push_str(&format!("#line {}\n", line_no));
+        }
+        self.src.push_str(line);
+        self.src.push('\n');
+        self.next_line = line_no + 1;
+    }
+
+    /// Null-terminated source
+    fn finish(mut self) -> String {
+        self.src.push('\0');
+        self.src
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "\
+@block common
+uniform sampler2D tex;
+@end
+
+@vs vs
+uniform vs_params {
+    mat4 mvp;
+    vec4 offsets[2];
+};
+
+layout(location=0) in vec3 pos;
+layout(location=1) out vec2 uv;
+
+void main() {}
+@end
+
+@fs fs
+@include_block common
+layout(location=0) in vec2 uv;
+out vec4 frag_color;
+
+void main() {}
+@end
+
+@program quad vs fs
+";
+
+    #[test]
+    fn program() {
+        let file = self::parse(SRC).unwrap();
+        assert_eq!(file.programs.len(), 1);
+
+        let prog = file.program("quad").unwrap();
+        assert_eq!(prog.vs.name, "vs");
+        assert_eq!(prog.fs.name, "fs");
+        assert!(file.program("vs").is_none());
+    }
+
+    #[test]
+    fn uniform_block() {
+        let file = self::parse(SRC).unwrap();
+        let vs = &file.program("quad").unwrap().vs;
+
+        assert_eq!(vs.blocks.len(), 1);
+        let block = &vs.blocks[0];
+        assert_eq!(block.name, "vs_params");
+        assert_eq!(
+            block.uniforms,
+            vec![
+                ShdcUniform {
+                    name: "mvp\0".to_string(),
+                    ty: rg::UniformType::Mat4,
+                    count: 1,
+                },
+                ShdcUniform {
+                    name: "offsets\0".to_string(),
+                    ty: rg::UniformType::Float4,
+                    count: 2,
+                },
+            ]
+        );
+        assert_eq!(block.size(), 64 + 32);
+
+        // flattened into named uniforms
+        assert!(vs.src.contains("uniform mat4 mvp;\n"));
+        assert!(vs.src.contains("uniform vec4 offsets[2];\n"));
+        assert!(!vs.src.contains("vs_params"));
+    }
+
+    #[test]
+    fn included_block_and_images() {
+        let file = self::parse(SRC).unwrap();
+        let fs = &file.program("quad").unwrap().fs;
+
+        assert!(fs.blocks.is_empty());
+        assert_eq!(
+            fs.images,
+            vec![ShdcImage {
+                name: "tex\0".to_string(),
+                ty: rg::ImageType::Dim2,
+            }]
+        );
+        assert!(fs.src.contains("uniform sampler2D tex;\n"));
+    }
+
+    #[test]
+    fn translated_source() {
+        let file = self::parse(SRC).unwrap();
+        let prog = file.program("quad").unwrap();
+
+        assert!(prog.vs.src.starts_with("#version 330\n"));
+        assert!(prog.vs.src.ends_with('\0'));
+        // vertex inputs keep their locations, varyings are matched by name
+        assert!(prog.vs.src.contains("layout(location=0) in vec3 pos;\n"));
+        assert!(prog.vs.src.contains("\nout vec2 uv;\n"));
+        assert!(prog.fs.src.contains("\nin vec2 uv;\n"));
+        // `#line` refers to the annotated file
+        assert!(prog.vs.src.contains("#line 7\nuniform mat4 mvp;\n"));
+        assert!(prog.fs.src.contains("#line 19\nin vec2 uv;\n"));
+        // the included block is at line 2, right after `#version`
+        assert!(prog
+            .fs
+            .src
+            .starts_with("#version 330\nuniform sampler2D tex;\n"));
+    }
+
+    #[test]
+    fn errors() {
+        let line = |src: &str| self::parse(src).unwrap_err().line;
+
+        assert_eq!(line("void main() {}"), 1);
+        assert_eq!(line("@vs vs\n@fs fs\n@end"), 2);
+        assert_eq!(line("@end"), 1);
+        assert_eq!(line("@vs vs\nvoid main() {}"), 2);
+        assert_eq!(line("@vs vs\n@include_block common\n@end"), 2);
+        assert_eq!(line("@vs vs\n@end\n@program p vs fs"), 3);
+        assert_eq!(line("@unknown"), 1);
+        assert_eq!(
+            line("@vs vs\nuniform float x;\n@end\n@fs fs\n@end\n@program p vs fs"),
+            2
+        );
+    }
+}
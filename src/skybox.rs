@@ -0,0 +1,116 @@
+/*!
+Skybox
+
+Draw it after the scene so that only the uncovered pixels are shaded. The scene pipelines have to
+write depth:
+
+```ignore
+// draw the scene
+self.skybox.draw(&self.cam);
+rg::end_pass();
+```
+*/
+
+use glam::Mat4;
+
+use crate::{
+    camera::Camera,
+    gfx::{Shader, StaticMesh, TextureCube},
+    shaders::{self, SkyboxUniforms, SkyboxVertex},
+};
+
+/// Removes the translation from a view matrix so that the skybox follows the camera
+pub fn strip_translation(view: Mat4) -> Mat4 {
+    let mut cols = view.to_cols_array();
+    cols[12] = 0.0;
+    cols[13] = 0.0;
+    cols[14] = 0.0;
+    Mat4::from_cols_array(&cols)
+}
+
+/// Cube of size 2 centered at the origin
+fn gen_skybox_mesh() -> StaticMesh<SkyboxVertex> {
+    StaticMesh::new_16(
+        &[
+            [-1.0, -1.0, -1.0].into(),
+            [1.0, -1.0, -1.0].into(),
+            [1.0, 1.0, -1.0].into(),
+            [-1.0, 1.0, -1.0].into(),
+            [-1.0, -1.0, 1.0].into(),
+            [1.0, -1.0, 1.0].into(),
+            [1.0, 1.0, 1.0].into(),
+            [-1.0, 1.0, 1.0].into(),
+        ],
+        &[
+            0, 1, 2, 0, 2, 3, // -Z
+            4, 6, 5, 4, 7, 6, // +Z
+            0, 3, 7, 0, 7, 4, // -X
+            1, 5, 6, 1, 6, 2, // +X
+            0, 4, 5, 0, 5, 1, // -Y
+            3, 2, 6, 3, 6, 7, // +Y
+        ],
+    )
+}
+
+/// Cubemap drawn behind everything
+#[derive(Debug)]
+pub struct Skybox {
+    shd: Shader,
+    mesh: StaticMesh<SkyboxVertex>,
+    tex: TextureCube,
+}
+
+impl Skybox {
+    pub fn new(tex: TextureCube) -> Self {
+        let mut mesh = self::gen_skybox_mesh();
+        mesh.bind_img(tex.img(), 0);
+
+        Self {
+            shd: shaders::skybox(),
+            mesh,
+            tex,
+        }
+    }
+
+    pub fn tex(&self) -> &TextureCube {
+        &self.tex
+    }
+
+    pub fn shd_mut(&mut self) -> &mut Shader {
+        &mut self.shd
+    }
+
+    /// Draws the skybox into the current pass. Call it after drawing the scene
+    pub fn draw(&self, cam: &impl Camera) {
+        let view_proj = cam.proj() * self::strip_translation(cam.view());
+
+        self.shd.apply_pip();
+        self.shd.apply_vs(&SkyboxUniforms { view_proj });
+        self.mesh.draw_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::Vec3;
+
+    #[test]
+    fn strip_translation_keeps_rotation() {
+        let view = Mat4::look_at_rh(Vec3::new(3.0, 2.0, 5.0), Vec3::zero(), Vec3::unit_y());
+        let stripped = self::strip_translation(view);
+
+        let (cols, orig) = (stripped.to_cols_array(), view.to_cols_array());
+        // translation column
+        assert_eq!(cols[12..], [0.0, 0.0, 0.0, 1.0]);
+        // rotation columns
+        assert_eq!(cols[..12], orig[..12]);
+
+        let dir = Vec3::new(1.0, -2.0, 0.5);
+        assert_eq!(stripped.transform_point3(Vec3::zero()), Vec3::zero());
+        assert!(stripped
+            .transform_vector3(dir)
+            .abs_diff_eq(view.transform_vector3(dir), 1e-6));
+    }
+}
@@ -0,0 +1,376 @@
+/*!
+Frame timing: delta time, fixed timestep and frame time statistics
+
+The clock is abstracted with [`Clock`] so that [`ManualClock`] can drive [`Time`] without waiting.
+*/
+
+use std::{collections::VecDeque, time::Duration, time::Instant};
+
+/// Source of the current time
+pub trait Clock {
+    /// Time since some fixed point
+    fn now(&self) -> Duration;
+}
+
+/// Real time clock
+#[derive(Debug, Clone)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Clock advanced manually
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Duration,
+}
+
+impl ManualClock {
+    pub fn advance(&mut self, dt: Duration) {
+        self.now += dt;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now
+    }
+}
+
+/// Frame time statistics over the recent frames
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    frames: VecDeque<Duration>,
+    cap: usize,
+    sum: Duration,
+}
+
+impl FrameStats {
+    /// * `cap`: number of frames to keep
+    pub fn new(cap: usize) -> Self {
+        assert!(cap > 0);
+        Self {
+            frames: VecDeque::with_capacity(cap),
+            cap,
+            sum: Duration::default(),
+        }
+    }
+
+    pub fn push(&mut self, dt: Duration) {
+        if self.frames.len() == self.cap {
+            let old = self.frames.pop_front().unwrap();
+            self.sum -= old;
+        }
+        self.frames.push_back(dt);
+        self.sum += dt;
+    }
+
+    /// Number of frames in the statistics
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn min(&self) -> Duration {
+        self.frames.iter().copied().min().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.frames.iter().copied().max().unwrap_or_default()
+    }
+
+    pub fn avg(&self) -> Duration {
+        if self.frames.is_empty() {
+            Duration::default()
+        } else {
+            self.sum / self.frames.len() as u32
+        }
+    }
+
+    /// Frames per second from the average frame time
+    pub fn fps(&self) -> f32 {
+        let avg = self.avg().as_secs_f32();
+        if avg > 0.0 {
+            1.0 / avg
+        } else {
+            0.0
+        }
+    }
+
+    /// Logs FPS and frame time min/avg/max in milliseconds
+    pub fn log(&self) {
+        log::info!(
+            "FPS: {:.1}, frame time (ms): min {:.2}, avg {:.2}, max {:.2}",
+            self.fps(),
+            self.min().as_secs_f64() * 1000.0,
+            self.avg().as_secs_f64() * 1000.0,
+            self.max().as_secs_f64() * 1000.0,
+        );
+    }
+}
+
+/// Accumulator for deterministic updates with fixed delta time
+///
+/// ```ignore
+/// fixed.accumulate(time.dt());
+/// while fixed.step() {
+///     update(fixed.dt());
+/// }
+/// render(fixed.alpha());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedStep {
+    dt: Duration,
+    acc: Duration,
+    /// Upper bound of accumulated time to avoid spiral of death
+    max_acc: Duration,
+}
+
+impl FixedStep {
+    /// Panics if `dt` is zero
+    pub fn new(dt: Duration) -> Self {
+        assert!(
+            dt > Duration::default(),
+            "fixed delta time must be positive"
+        );
+        Self {
+            dt,
+            acc: Duration::default(),
+            max_acc: dt * 8,
+        }
+    }
+
+    /// Fixed delta time
+    pub fn dt(&self) -> Duration {
+        self.dt
+    }
+
+    /// Sets the maximum number of steps run on one frame
+    pub fn set_max_steps(&mut self, n: u32) {
+        self.max_acc = self.dt * n;
+    }
+
+    /// Adds frame delta time
+    pub fn accumulate(&mut self, dt: Duration) {
+        self.acc = (self.acc + dt).min(self.max_acc);
+    }
+
+    /// Consumes one step if available
+    pub fn step(&mut self) -> bool {
+        if self.acc >= self.dt {
+            self.acc -= self.dt;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Interpolation factor in [0, 1) between the previous and the current step
+    pub fn alpha(&self) -> f32 {
+        self.acc.as_secs_f32() / self.dt.as_secs_f32()
+    }
+}
+
+/// Frame clock. Call [`Time::tick`] at the beginning of every frame
+#[derive(Debug, Clone)]
+pub struct Time<C: Clock = SystemClock> {
+    clock: C,
+    start: Duration,
+    last: Duration,
+    dt: Duration,
+    frame_count: u64,
+    stats: FrameStats,
+    /// Interval to log statistics, if any
+    log_interval: Option<Duration>,
+    last_log: Duration,
+}
+
+impl Time<SystemClock> {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock::default())
+    }
+}
+
+impl Default for Time<SystemClock> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> Time<C> {
+    pub fn with_clock(clock: C) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            start: now,
+            last: now,
+            dt: Duration::default(),
+            frame_count: 0,
+            stats: FrameStats::new(120),
+            log_interval: None,
+            last_log: now,
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn clock_mut(&mut self) -> &mut C {
+        &mut self.clock
+    }
+
+    /// Logs [`FrameStats`] every `interval` (or never if `None`)
+    pub fn set_log_interval(&mut self, interval: Option<Duration>) {
+        self.log_interval = interval;
+    }
+
+    /// Advances to the next frame
+    pub fn tick(&mut self) {
+        let now = self.clock.now();
+        self.dt = now - self.last;
+        self.last = now;
+        self.frame_count += 1;
+        self.stats.push(self.dt);
+
+        if let Some(interval) = self.log_interval {
+            if now - self.last_log >= interval {
+                self.last_log = now;
+                self.stats.log();
+            }
+        }
+    }
+
+    /// Delta time of the current frame
+    pub fn dt(&self) -> Duration {
+        self.dt
+    }
+
+    /// Delta time of the current frame in seconds
+    pub fn dt_secs(&self) -> f32 {
+        self.dt.as_secs_f32()
+    }
+
+    /// Time since the creation until the last tick
+    pub fn elapsed(&self) -> Duration {
+        self.last - self.start
+    }
+
+    /// Number of ticks
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(x: u64) -> Duration {
+        Duration::from_millis(x)
+    }
+
+    #[test]
+    fn time_with_manual_clock() {
+        let mut time = Time::with_clock(ManualClock::default());
+
+        time.clock_mut().advance(ms(16));
+        time.tick();
+        assert_eq!(time.dt(), ms(16));
+
+        time.clock_mut().advance(ms(20));
+        time.tick();
+        assert_eq!(time.dt(), ms(20));
+        assert_eq!(time.elapsed(), ms(36));
+        assert_eq!(time.frame_count(), 2);
+        assert_eq!(time.stats().max(), ms(20));
+    }
+
+    #[test]
+    fn frame_stats_keep_recent_frames() {
+        let mut stats = FrameStats::new(2);
+        for x in &[10, 20, 30] {
+            stats.push(ms(*x));
+        }
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats.min(), ms(20));
+        assert_eq!(stats.avg(), ms(25));
+        assert!((stats.fps() - 40.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn fixed_step_accumulates() {
+        let mut fixed = FixedStep::new(ms(10));
+
+        fixed.accumulate(ms(25));
+        assert!(fixed.step());
+        assert!(fixed.step());
+        assert!(!fixed.step());
+        assert!((fixed.alpha() - 0.5).abs() < 1e-6);
+
+        // the remainder is carried over
+        fixed.accumulate(ms(5));
+        assert!(fixed.step());
+        assert!(!fixed.step());
+        assert_eq!(fixed.alpha(), 0.0);
+    }
+
+    #[test]
+    fn fixed_step_clamps_steps_per_frame() {
+        let mut fixed = FixedStep::new(ms(10));
+        fixed.set_max_steps(3);
+        fixed.accumulate(ms(1000));
+
+        let mut n = 0;
+        while fixed.step() {
+            n += 1;
+        }
+        assert_eq!(n, 3);
+    }
+
+    #[test]
+    fn fixed_step_driven_by_time() {
+        let mut time = Time::with_clock(ManualClock::default());
+        let mut fixed = FixedStep::new(ms(10));
+
+        let mut n = 0;
+        for _ in 0..4 {
+            time.clock_mut().advance(ms(15));
+            time.tick();
+            fixed.accumulate(time.dt());
+            while fixed.step() {
+                n += 1;
+            }
+        }
+
+        assert_eq!(n, 6);
+        assert_eq!(fixed.alpha(), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn fixed_step_rejects_zero_dt() {
+        FixedStep::new(Duration::default());
+    }
+}